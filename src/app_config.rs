@@ -12,6 +12,7 @@ const BASE_PATH_DIR_NAME: &str = "gdrive3";
 const ACCOUNT_CONFIG_NAME: &str = "account.json";
 const SECRET_CONFIG_NAME: &str = "secret.json";
 const TOKENS_CONFIG_NAME: &str = "tokens.json";
+const CHANGES_DIR_NAME: &str = "changes";
 
 #[derive(Debug, Clone)]
 pub struct AppConfig {
@@ -156,6 +157,13 @@ impl AppConfig {
         self.account_base_path().join(TOKENS_CONFIG_NAME)
     }
 
+    // Files that keep the position in the change feed of commands that watch for changes
+    pub fn changes_state_path(&self, name: &str) -> PathBuf {
+        self.account_base_path()
+            .join(CHANGES_DIR_NAME)
+            .join(format!("{}.json", name))
+    }
+
     pub fn default_base_path() -> Result<PathBuf, Error> {
         let home_path = home::home_dir().ok_or(Error::HomeDirNotFound)?;
         let base_path = home_path
@@ -0,0 +1,7 @@
+pub mod list;
+pub mod start_token;
+pub mod watch;
+
+pub use list::list;
+pub use start_token::start_token;
+pub use watch::watch;
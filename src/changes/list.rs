@@ -0,0 +1,338 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::hub_helper;
+use crate::common::table;
+use crate::common::table::Table;
+use crate::files;
+use crate::files::list::ListFilesConfig;
+use crate::files::list::ListQuery;
+use crate::files::list::ListSortOrder;
+use crate::hub::Hub;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+
+const MAX_PAGE_SIZE: i32 = 1000;
+
+pub const CHANGE_FIELDS: &str = "nextPageToken,newStartPageToken,changes(changeType,time,removed,fileId,driveId,file(id,name,mimeType,parents,trashed,size,md5Checksum,createdTime,modifiedTime))";
+
+pub struct Config {
+    pub since: String,
+    pub drive_id: Option<String>,
+    pub folder_id: Option<String>,
+    pub skip_header: bool,
+    pub field_separator: String,
+}
+
+pub async fn list(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let changes = list_changes(
+        &hub,
+        delegate_config,
+        &ListChangesConfig {
+            page_token: config.since.clone(),
+            drive_id: config.drive_id.clone(),
+        },
+    )
+    .await?;
+
+    let mut filter = ChangeFilter::new(config.folder_id.clone());
+
+    let events: Vec<ChangeEvent> = changes
+        .changes
+        .iter()
+        .filter_map(|change| filter.event(change))
+        .collect();
+
+    print_events_table(&config, events);
+
+    println!();
+    println!("Next token: {}", changes.new_start_page_token);
+
+    Ok(())
+}
+
+fn print_events_table(config: &Config, events: Vec<ChangeEvent>) {
+    let mut values: Vec<[String; 4]> = vec![];
+
+    for event in events {
+        values.push([
+            event
+                .time
+                .map(files::info::format_date_time)
+                .unwrap_or_default(),
+            event.kind.to_string(),
+            event.file_id,
+            event.name.unwrap_or_default(),
+        ])
+    }
+
+    let table = Table {
+        header: ["Time", "Event", "Id", "Name"],
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: config.field_separator.clone(),
+        },
+    );
+}
+
+#[derive(Debug, Clone)]
+pub struct ListChangesConfig {
+    pub page_token: String,
+    pub drive_id: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChangesPage {
+    pub changes: Vec<google_drive3::api::Change>,
+    // Token to use the next time changes are listed
+    pub new_start_page_token: String,
+}
+
+// Lists all changes since the page token. Only file changes are returned,
+// changes to the shared drives themselves are skipped
+pub async fn list_changes(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    config: &ListChangesConfig,
+) -> Result<ChangesPage, Error> {
+    let mut collected_changes: Vec<google_drive3::api::Change> = vec![];
+    let mut page_token = config.page_token.clone();
+
+    loop {
+        let mut delegate = UploadDelegate::new(delegate_config.clone());
+
+        let mut req = hub
+            .changes()
+            .list(&page_token)
+            .page_size(MAX_PAGE_SIZE)
+            .include_removed(true)
+            .include_items_from_all_drives(true)
+            .supports_all_drives(true);
+
+        if let Some(drive_id) = &config.drive_id {
+            req = req.drive_id(drive_id);
+        }
+
+        let (_, change_list) = req
+            .add_scope(google_drive3::api::Scope::Full)
+            .delegate(&mut delegate)
+            .param("fields", CHANGE_FIELDS)
+            .doit()
+            .await
+            .map_err(Error::ListChanges)?;
+
+        if let Some(changes) = change_list.changes {
+            let file_changes = changes
+                .into_iter()
+                .filter(|change| change.change_type.as_deref() != Some("drive"));
+
+            collected_changes.extend(file_changes);
+        }
+
+        match (
+            change_list.next_page_token,
+            change_list.new_start_page_token,
+        ) {
+            (Some(next_page_token), _) => {
+                // fmt
+                page_token = next_page_token;
+            }
+
+            (None, Some(new_start_page_token)) => {
+                return Ok(ChangesPage {
+                    changes: collected_changes,
+                    new_start_page_token,
+                });
+            }
+
+            (None, None) => return Err(Error::MissingNewStartPageToken),
+        }
+    }
+}
+
+// Keeps the changes to files directly in the folder. Removed files don't have parents,
+// so they only match if the filter knows that they were in the folder
+#[derive(Debug, Clone, Default)]
+pub struct ChangeFilter {
+    folder_id: Option<String>,
+    known_ids: HashSet<String>,
+    // Set if the known ids were listed before the first change. Files that are not known are
+    // then reported as added, instead of guessing from their timestamps
+    knows_folder_content: bool,
+}
+
+impl ChangeFilter {
+    pub fn new(folder_id: Option<String>) -> ChangeFilter {
+        ChangeFilter {
+            folder_id,
+            known_ids: HashSet::new(),
+            knows_folder_content: false,
+        }
+    }
+
+    // Filter for a folder with the ids of the files that were in it when the changes started
+    pub fn with_known_ids(folder_id: String, known_ids: HashSet<String>) -> ChangeFilter {
+        ChangeFilter {
+            folder_id: Some(folder_id),
+            known_ids,
+            knows_folder_content: true,
+        }
+    }
+
+    pub fn known_ids(&self) -> &HashSet<String> {
+        &self.known_ids
+    }
+
+    // Returns the event for the change if it passes the filter
+    pub fn event(&mut self, change: &google_drive3::api::Change) -> Option<ChangeEvent> {
+        let event = ChangeEvent::from_change(change)?;
+
+        let folder_id = match &self.folder_id {
+            Some(folder_id) => folder_id,
+            None => return Some(event),
+        };
+
+        if event.parents.contains(folder_id) {
+            let is_new = self.known_ids.insert(event.file_id.clone());
+
+            let kind = match event.kind {
+                ChangeKind::Added | ChangeKind::Modified if self.knows_folder_content => {
+                    if is_new {
+                        ChangeKind::Added
+                    } else {
+                        ChangeKind::Modified
+                    }
+                }
+
+                kind => kind,
+            };
+
+            Some(ChangeEvent { kind, ..event })
+        } else if self.known_ids.remove(&event.file_id) {
+            // A file that was moved out of the folder is reported as removed
+            Some(ChangeEvent {
+                kind: ChangeKind::Removed,
+                ..event
+            })
+        } else {
+            None
+        }
+    }
+}
+
+// Ids of the files directly in the folder, including the ones in the trash
+pub async fn list_folder_ids(hub: &Hub, folder_id: &str) -> Result<HashSet<String>, Error> {
+    let files = files::list::list_files(
+        hub,
+        &ListFilesConfig {
+            query: ListQuery::Custom(format!("'{}' in parents", folder_id)),
+            order_by: ListSortOrder::default(),
+            max_files: usize::MAX,
+            fields: Some(String::from("id")),
+        },
+    )
+    .await
+    .map_err(Error::ListFolder)?;
+
+    Ok(files.into_iter().filter_map(|file| file.id).collect())
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+    Trashed,
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Modified => write!(f, "modified"),
+            ChangeKind::Removed => write!(f, "removed"),
+            ChangeKind::Trashed => write!(f, "trashed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEvent {
+    #[serde(rename = "event")]
+    pub kind: ChangeKind,
+    pub file_id: String,
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    pub parents: Vec<String>,
+    pub drive_id: Option<String>,
+    pub time: Option<google_drive3::chrono::DateTime<google_drive3::chrono::Utc>>,
+}
+
+impl ChangeEvent {
+    // The changes api doesn't tell new files apart from modified ones, files that have
+    // not been modified since they were created are reported as added. Uploads that keep the
+    // local modification time have an older modified than created time, so they are reported
+    // as modified. Filters that know the content of the folder correct this, see ChangeFilter
+    pub fn from_change(change: &google_drive3::api::Change) -> Option<ChangeEvent> {
+        let file_id = change.file_id.clone()?;
+        let file = change.file.clone().unwrap_or_default();
+
+        let kind = if change.removed.unwrap_or(false) {
+            ChangeKind::Removed
+        } else if file.trashed.unwrap_or(false) {
+            ChangeKind::Trashed
+        } else if file.created_time.is_some() && file.created_time == file.modified_time {
+            ChangeKind::Added
+        } else {
+            ChangeKind::Modified
+        };
+
+        Some(ChangeEvent {
+            kind,
+            file_id,
+            name: file.name,
+            mime_type: file.mime_type,
+            parents: file.parents.unwrap_or_default(),
+            drive_id: change.drive_id.clone(),
+            time: change.time,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    ListChanges(google_drive3::Error),
+    ListFolder(files::list::Error),
+    MissingNewStartPageToken,
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::ListChanges(err) => write!(f, "Failed to list changes: {}", err),
+            Error::ListFolder(err) => write!(f, "Failed to list files in folder: {}", err),
+            Error::MissingNewStartPageToken => {
+                write!(f, "Drive did not return a token for the next changes")
+            }
+        }
+    }
+}
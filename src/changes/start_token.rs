@@ -0,0 +1,70 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::hub_helper;
+use crate::hub::Hub;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+pub struct Config {
+    pub drive_id: Option<String>,
+}
+
+pub async fn start_token(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let token = get_start_page_token(&hub, delegate_config, &config.drive_id).await?;
+    println!("{}", token);
+
+    Ok(())
+}
+
+// Returns a token for the current state of the user's files, or of the shared drive if given.
+// Changes made after this point are returned when listing changes with the token
+pub async fn get_start_page_token(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    drive_id: &Option<String>,
+) -> Result<String, Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let mut req = hub.changes().get_start_page_token();
+
+    if let Some(drive_id) = drive_id {
+        req = req.drive_id(drive_id);
+    }
+
+    let (_, start_page_token) = req
+        .supports_all_drives(true)
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .doit()
+        .await
+        .map_err(Error::GetStartPageToken)?;
+
+    start_page_token
+        .start_page_token
+        .ok_or(Error::MissingStartPageToken)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    GetStartPageToken(google_drive3::Error),
+    MissingStartPageToken,
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::GetStartPageToken(err) => write!(f, "Failed to get start page token: {}", err),
+            Error::MissingStartPageToken => {
+                write!(f, "Drive did not return a start page token")
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::app_config;
+use crate::app_config::AppConfig;
+use crate::changes::list;
+use crate::changes::list::ChangeEvent;
+use crate::changes::list::ChangeFilter;
+use crate::changes::list::ListChangesConfig;
+use crate::changes::start_token;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::hub_helper;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::process::Command;
+
+pub struct Config {
+    pub drive_id: Option<String>,
+    pub folder_id: Option<String>,
+    pub interval: Duration,
+    pub hook: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchState {
+    page_token: String,
+    // Files in the watched folder, used to tell added files apart from modified ones
+    #[serde(default)]
+    known_ids: Option<HashSet<String>>,
+}
+
+pub async fn watch(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let app_cfg = AppConfig::load_current_account().map_err(Error::AppConfig)?;
+    let state_path = app_cfg.changes_state_path(&state_name(&config));
+
+    // Without a saved token the watch starts with the changes made from now on
+    let state = match load_state(&state_path)? {
+        Some(state) => state,
+        None => {
+            let token =
+                start_token::get_start_page_token(&hub, delegate_config.clone(), &config.drive_id)
+                    .await
+                    .map_err(Error::StartToken)?;
+
+            WatchState {
+                page_token: token,
+                known_ids: None,
+            }
+        }
+    };
+
+    let mut page_token = state.page_token;
+
+    // The content of the folder is listed once, later changes keep the known ids up to date
+    let mut filter = match (&config.folder_id, state.known_ids) {
+        (Some(folder_id), Some(known_ids)) => {
+            ChangeFilter::with_known_ids(folder_id.clone(), known_ids)
+        }
+
+        (Some(folder_id), None) => {
+            let known_ids = list::list_folder_ids(&hub, folder_id)
+                .await
+                .map_err(Error::ListFolder)?;
+
+            ChangeFilter::with_known_ids(folder_id.clone(), known_ids)
+        }
+
+        (None, _) => ChangeFilter::new(None),
+    };
+
+    save_state(&state_path, &page_token, &filter)?;
+
+    loop {
+        let list_config = ListChangesConfig {
+            page_token: page_token.clone(),
+            drive_id: config.drive_id.clone(),
+        };
+
+        // Failures to reach drive are retried on the next poll
+        match list::list_changes(&hub, delegate_config.clone(), &list_config).await {
+            Ok(changes) => {
+                let events: Vec<ChangeEvent> = changes
+                    .changes
+                    .iter()
+                    .filter_map(|change| filter.event(change))
+                    .collect();
+
+                for event in events {
+                    emit_event(&config, &event).await?;
+                }
+
+                page_token = changes.new_start_page_token;
+                save_state(&state_path, &page_token, &filter)?;
+            }
+
+            Err(err) => eprintln!("Warning: {}", err),
+        }
+
+        tokio::time::sleep(config.interval).await;
+    }
+}
+
+// Prints the event as a json line, or passes it to the hook command through environment variables
+async fn emit_event(config: &Config, event: &ChangeEvent) -> Result<(), Error> {
+    let json = serde_json::to_string(event).map_err(Error::SerializeEvent)?;
+
+    let hook = match &config.hook {
+        Some(hook) => hook,
+        None => {
+            println!("{}", json);
+            return Ok(());
+        }
+    };
+
+    let mut command = shell_command(hook);
+    command
+        .env("GDRIVE_EVENT", event.kind.to_string())
+        .env("GDRIVE_FILE_ID", &event.file_id)
+        .env("GDRIVE_FILE_NAME", event.name.clone().unwrap_or_default())
+        .env(
+            "GDRIVE_MIME_TYPE",
+            event.mime_type.clone().unwrap_or_default(),
+        )
+        .env("GDRIVE_PARENTS", event.parents.join(","))
+        .env(
+            "GDRIVE_DRIVE_ID",
+            event.drive_id.clone().unwrap_or_default(),
+        )
+        .env("GDRIVE_EVENT_JSON", &json);
+
+    match command.status().await {
+        Ok(status) if status.success() => {}
+
+        Ok(status) => eprintln!(
+            "Warning: Hook failed for {} event of {}: {}",
+            event.kind, event.file_id, status
+        ),
+
+        Err(err) => return Err(Error::RunHook(err)),
+    }
+
+    Ok(())
+}
+
+fn shell_command(hook: &str) -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(hook);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(hook);
+        command
+    }
+}
+
+// Watches with different filters keep separate tokens
+fn state_name(config: &Config) -> String {
+    let mut name = String::from("watch");
+
+    if let Some(drive_id) = &config.drive_id {
+        name.push_str(&format!("_drive-{}", drive_id));
+    }
+
+    if let Some(folder_id) = &config.folder_id {
+        name.push_str(&format!("_folder-{}", folder_id));
+    }
+
+    name
+}
+
+fn load_state(path: &PathBuf) -> Result<Option<WatchState>, Error> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path).map_err(|err| Error::ReadState(path.clone(), err))?;
+
+    let state = serde_json::from_str(&content).map_err(Error::DeserializeState)?;
+    Ok(Some(state))
+}
+
+fn save_state(path: &PathBuf, page_token: &str, filter: &ChangeFilter) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| Error::WriteState(path.clone(), err))?;
+    }
+
+    let state = WatchState {
+        page_token: page_token.to_string(),
+        known_ids: Some(filter.known_ids().clone()),
+    };
+
+    let content = serde_json::to_string_pretty(&state).map_err(Error::SerializeState)?;
+    fs::write(path, content).map_err(|err| Error::WriteState(path.clone(), err))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    AppConfig(app_config::Error),
+    StartToken(start_token::Error),
+    ListFolder(list::Error),
+    ReadState(PathBuf, io::Error),
+    WriteState(PathBuf, io::Error),
+    SerializeState(serde_json::Error),
+    DeserializeState(serde_json::Error),
+    SerializeEvent(serde_json::Error),
+    RunHook(io::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::AppConfig(err) => write!(f, "{}", err),
+            Error::StartToken(err) => write!(f, "{}", err),
+            Error::ListFolder(err) => write!(f, "{}", err),
+            Error::ReadState(path, err) => {
+                write!(f, "Failed to read '{}': {}", path.display(), err)
+            }
+            Error::WriteState(path, err) => {
+                write!(f, "Failed to write '{}': {}", path.display(), err)
+            }
+            Error::SerializeState(err) => write!(f, "Failed to serialize watch state: {}", err),
+            Error::DeserializeState(err) => {
+                write!(f, "Failed to deserialize watch state: {}", err)
+            }
+            Error::SerializeEvent(err) => write!(f, "Failed to serialize event: {}", err),
+            Error::RunHook(err) => write!(f, "Failed to run hook: {}", err),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::common::drive_batch;
+use crate::common::failure_report::PARTIAL_FAILURE_EXIT_CODE;
+use crate::common::hub_helper;
+use crate::common::table;
+use crate::common::table::Table;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::PathBuf;
+
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+// Collects the ids given as arguments and the ids read from a file, or stdin if the path is '-'.
+// The file contains one id per line, empty lines and lines starting with '#' are ignored
+pub fn read_ids(ids: &[String], ids_from: &Option<PathBuf>) -> Result<Vec<String>, Error> {
+    let mut all_ids = ids.to_vec();
+
+    if let Some(path) = ids_from {
+        let content = read_input(path)?;
+        all_ids.extend(parse_lines(&content));
+    }
+
+    Ok(all_ids)
+}
+
+// Reads the lines of a file, or stdin if the path is '-', skipping empty lines and comments
+pub fn read_lines(path: &PathBuf) -> Result<Vec<String>, Error> {
+    let content = read_input(path)?;
+    Ok(parse_lines(&content))
+}
+
+fn read_input(path: &PathBuf) -> Result<String, Error> {
+    if path.as_os_str() == "-" {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(Error::ReadStdin)?;
+        Ok(content)
+    } else {
+        fs::read_to_string(path).map_err(|err| Error::ReadIds(path.clone(), err))
+    }
+}
+
+fn parse_lines(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub succeeded: usize,
+    pub failures: Vec<(String, String)>,
+}
+
+impl Summary {
+    pub fn add_success(&mut self) {
+        self.succeeded += 1;
+    }
+
+    pub fn add_failure(&mut self, item: &str, err: &impl Display) {
+        eprintln!("Warning: Failed '{}': {}", item, err);
+        self.failures.push((item.to_string(), err.to_string()));
+    }
+
+    // Prints the failed items and returns an error if any of the items failed
+    pub fn finish(self) -> Result<(), Error> {
+        let total = self.succeeded + self.failures.len();
+
+        if !self.failures.is_empty() {
+            let table = Table {
+                header: ["Item", "Error"],
+                values: self
+                    .failures
+                    .iter()
+                    .map(|(item, err)| [item.clone(), err.clone()])
+                    .collect(),
+            };
+
+            eprintln!();
+            let _ = table::write(io::stderr(), table, &table::DisplayConfig::default());
+        }
+
+        println!();
+        println!(
+            "{} of {} items succeeded, {} failed",
+            self.succeeded,
+            total,
+            self.failures.len()
+        );
+
+        if self.failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::PartialFailure(self.failures.len(), total))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    DriveBatch(drive_batch::Error),
+    ReadIds(PathBuf, io::Error),
+    ReadStdin(io::Error),
+    InvalidLine(String, String),
+    InvalidOptions(String),
+    NoIds,
+    PartialFailure(usize, usize),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::PartialFailure(_, _) => PARTIAL_FAILURE_EXIT_CODE,
+            _ => 1,
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::DriveBatch(err) => write!(f, "{}", err),
+            Error::ReadIds(path, err) => {
+                write!(f, "Failed to read ids from '{}': {}", path.display(), err)
+            }
+            Error::ReadStdin(err) => write!(f, "Failed to read ids from stdin: {}", err),
+            Error::InvalidLine(line, expected) => {
+                write!(f, "Invalid line '{}', expected '{}'", line, expected)
+            }
+            Error::InvalidOptions(err) => write!(f, "{}", err),
+            Error::NoIds => write!(f, "No file ids given"),
+            Error::PartialFailure(failed, total) => {
+                write!(f, "{} of {} items failed", failed, total)
+            }
+        }
+    }
+}
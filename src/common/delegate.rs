@@ -154,10 +154,14 @@ impl Backoff {
         }
     }
 
-    fn retry(&mut self) -> google_drive3::client::Retry {
+    // Returns how long to wait before the next attempt, or none if there are no retries left
+    pub fn next_delay(&mut self) -> Option<Duration> {
         self.attempts += 1;
-        self.backoff
-            .next(self.attempts)
+        self.backoff.next(self.attempts)
+    }
+
+    fn retry(&mut self) -> google_drive3::client::Retry {
+        self.next_delay()
             .map(google_drive3::client::Retry::After)
             .unwrap_or(google_drive3::client::Retry::Abort)
     }
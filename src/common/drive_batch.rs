@@ -0,0 +1,757 @@
+use crate::common::delegate::Backoff;
+use crate::common::delegate::BackoffConfig;
+use crate::hub::Hub;
+use futures::stream;
+use futures::stream::StreamExt;
+use google_drive3::hyper;
+use google_drive3::hyper::http;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cmp::max;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+// Drive rejects batches with more than 100 calls
+pub const MAX_BATCH_SIZE: usize = 100;
+
+const BATCH_URL: &str = "https://www.googleapis.com/batch/drive/v3";
+const API_URL: &str = "https://www.googleapis.com";
+const BOUNDARY: &str = "gdrive_batch_boundary";
+const MAX_RETRIES: u32 = 5;
+
+// Drive reports exceeded rate limits as 403 with one of these reasons
+const RATE_LIMIT_REASONS: [&str; 2] = ["userRateLimitExceeded", "rateLimitExceeded"];
+
+// A single api call that is sent as one part of a batch request
+#[derive(Debug, Clone)]
+pub struct Call {
+    pub method: http::Method,
+    pub path: String,
+    pub body: Option<serde_json::Value>,
+}
+
+impl Call {
+    pub fn get_file(file_id: &str, fields: &str) -> Call {
+        Call {
+            method: http::Method::GET,
+            path: files_path(&[file_id], &[("fields", fields)]),
+            body: None,
+        }
+    }
+
+    pub fn delete_file(file_id: &str) -> Call {
+        Call {
+            method: http::Method::DELETE,
+            path: files_path(&[file_id], &[]),
+            body: None,
+        }
+    }
+
+    pub fn update_file(
+        file_id: &str,
+        file: &google_drive3::api::File,
+        query: &[(&str, &str)],
+    ) -> Result<Call, Error> {
+        Ok(Call {
+            method: http::Method::PATCH,
+            path: files_path(&[file_id], query),
+            body: Some(json_body(file)?),
+        })
+    }
+
+    pub fn list_permissions(file_id: &str, query: &[(&str, &str)]) -> Call {
+        Call {
+            method: http::Method::GET,
+            path: files_path(&[file_id, "permissions"], query),
+            body: None,
+        }
+    }
+
+    pub fn create_permission(
+        file_id: &str,
+        permission: &google_drive3::api::Permission,
+        query: &[(&str, &str)],
+    ) -> Result<Call, Error> {
+        Ok(Call {
+            method: http::Method::POST,
+            path: files_path(&[file_id, "permissions"], query),
+            body: Some(json_body(permission)?),
+        })
+    }
+
+    pub fn update_permission(
+        file_id: &str,
+        permission_id: &str,
+        permission: &google_drive3::api::Permission,
+        query: &[(&str, &str)],
+    ) -> Result<Call, Error> {
+        Ok(Call {
+            method: http::Method::PATCH,
+            path: files_path(&[file_id, "permissions", permission_id], query),
+            body: Some(json_body(permission)?),
+        })
+    }
+
+    // Offers the ownership to a writer, who has to accept it. The generated client has no
+    // pendingOwner field, so the body is built by hand
+    pub fn set_pending_owner(file_id: &str, permission_id: &str) -> Call {
+        Call {
+            method: http::Method::PATCH,
+            path: files_path(
+                &[file_id, "permissions", permission_id],
+                &[("fields", "id")],
+            ),
+            body: Some(serde_json::json!({ "role": "writer", "pendingOwner": true })),
+        }
+    }
+
+    pub fn delete_permission(file_id: &str, permission_id: &str) -> Call {
+        Call {
+            method: http::Method::DELETE,
+            path: files_path(&[file_id, "permissions", permission_id], &[]),
+            body: None,
+        }
+    }
+}
+
+// Serializes the body of a call. Fields that are not set are left out instead of sent as null,
+// like the generated client does, as drive clears the fields that are null in a patch
+fn json_body<T: Serialize>(value: &T) -> Result<serde_json::Value, Error> {
+    let mut json = serde_json::to_value(value).map_err(Error::SerializeBody)?;
+    google_drive3::client::remove_json_null_values(&mut json);
+    Ok(json)
+}
+
+// Only creating calls have an effect when they are sent twice. Patches are included as
+// idempotent, the same patch gives the same result
+fn is_idempotent(method: &http::Method) -> bool {
+    method != http::Method::POST
+}
+
+// Error of a single call in a batch, the status is missing if the call got no response
+#[derive(Debug, Clone)]
+pub struct CallError {
+    pub status: Option<u16>,
+    // Reason of the first error in the response, like 'userRateLimitExceeded'
+    pub reason: Option<String>,
+    pub message: String,
+    // The batch request failed as a whole, drive may or may not have executed the call
+    pub outcome_unknown: bool,
+}
+
+impl CallError {
+    fn is_retryable(&self, method: &http::Method) -> bool {
+        if self.outcome_unknown && !is_idempotent(method) {
+            return false;
+        }
+
+        match self.status {
+            None => true,
+            Some(403) => self
+                .reason
+                .as_deref()
+                .is_some_and(|reason| RATE_LIMIT_REASONS.contains(&reason)),
+            Some(status) => status == 429 || status >= 500,
+        }
+    }
+
+    // Drive may have executed the call before failing with a server error or no response
+    fn may_have_been_applied(&self) -> bool {
+        self.outcome_unknown || self.status.is_none_or(|status| status >= 500)
+    }
+}
+
+impl error::Error for CallError {}
+
+impl Display for CallError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "{} ({})", self.message, status),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+pub type CallResult = Result<serde_json::Value, CallError>;
+
+// Executes the calls in batches of up to 100 calls. Calls that fail with a retryable
+// status are retried with exponential backoff, the others are returned as is.
+// The results are in the same order as the calls
+pub async fn execute(
+    hub: &Hub,
+    calls: &[Call],
+    concurrency: usize,
+) -> Result<Vec<CallResult>, Error> {
+    let mut results: Vec<Option<CallResult>> = vec![None; calls.len()];
+    let mut pending: Vec<usize> = (0..calls.len()).collect();
+    let mut maybe_applied = vec![false; calls.len()];
+    let mut backoff = Backoff::new(BackoffConfig {
+        max_retries: MAX_RETRIES,
+        ..BackoffConfig::default()
+    });
+
+    while !pending.is_empty() {
+        let token = get_token(hub).await?;
+
+        let batch_results: Vec<Result<Vec<(usize, CallResult)>, Error>> =
+            stream::iter(pending.chunks(MAX_BATCH_SIZE).map(|chunk| chunk.to_vec()))
+                .map(|indexes| send_batch(hub, &token, calls, indexes))
+                .buffer_unordered(max(concurrency, 1))
+                .collect()
+                .await;
+
+        let mut failed = vec![];
+
+        for batch_result in batch_results {
+            for (index, result) in batch_result? {
+                // A retried delete finds the item gone if an earlier attempt was applied after all
+                let result = match result {
+                    Err(err)
+                        if err.status == Some(404)
+                            && maybe_applied[index]
+                            && calls[index].method == http::Method::DELETE =>
+                    {
+                        Ok(serde_json::Value::Null)
+                    }
+
+                    result => result,
+                };
+
+                if matches!(&result, Err(err) if err.may_have_been_applied()) {
+                    maybe_applied[index] = true;
+                }
+
+                if matches!(&result, Err(err) if err.is_retryable(&calls[index].method)) {
+                    failed.push(index);
+                }
+
+                results[index] = Some(result);
+            }
+        }
+
+        pending = match backoff.next_delay() {
+            Some(delay) if !failed.is_empty() => {
+                tokio::time::sleep(delay).await;
+                failed
+            }
+
+            _ => {
+                // fmt
+                vec![]
+            }
+        };
+    }
+
+    let results = results
+        .into_iter()
+        .map(|result| {
+            result.unwrap_or(Err(CallError {
+                status: None,
+                reason: None,
+                message: String::from("No response"),
+                outcome_unknown: true,
+            }))
+        })
+        .collect();
+
+    Ok(results)
+}
+
+// Sends a single call as a plain request instead of a batch, for responses that need the raw
+// json of the api. Retryable failures are retried with exponential backoff like in a batch
+pub async fn send(hub: &Hub, call: &Call) -> Result<CallResult, Error> {
+    let mut backoff = Backoff::new(BackoffConfig {
+        max_retries: MAX_RETRIES,
+        ..BackoffConfig::default()
+    });
+
+    loop {
+        let token = get_token(hub).await?;
+        let result = send_call(hub, &token, call).await?;
+
+        match backoff.next_delay() {
+            Some(delay) if matches!(&result, Err(err) if err.is_retryable(&call.method)) => {
+                tokio::time::sleep(delay).await;
+            }
+
+            _ => return Ok(result),
+        }
+    }
+}
+
+async fn send_call(hub: &Hub, token: &str, call: &Call) -> Result<CallResult, Error> {
+    let body = match &call.body {
+        Some(body) => hyper::Body::from(body.to_string()),
+        None => hyper::Body::empty(),
+    };
+
+    let request = hyper::Request::builder()
+        .method(call.method.clone())
+        .uri(format!("{}{}", API_URL, call.path))
+        .header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .map_err(Error::BuildRequest)?;
+
+    let failure = |message: String| CallError {
+        status: None,
+        reason: None,
+        message,
+        outcome_unknown: true,
+    };
+
+    let response = match hub.client.request(request).await {
+        Ok(response) => response,
+        Err(err) => return Ok(Err(failure(err.to_string()))),
+    };
+
+    let status = response.status();
+
+    let bytes = match hyper::body::to_bytes(response.into_body()).await {
+        Ok(bytes) => bytes,
+        Err(err) => return Ok(Err(failure(err.to_string()))),
+    };
+
+    let body = String::from_utf8_lossy(&bytes);
+
+    let result = if status.is_success() {
+        Ok(serde_json::from_str(&body).unwrap_or(serde_json::Value::Null))
+    } else {
+        Err(CallError {
+            status: Some(status.as_u16()),
+            reason: error_reason(&body),
+            message: error_message(&body),
+            outcome_unknown: false,
+        })
+    };
+
+    Ok(result)
+}
+
+// Deserializes the response of a call
+pub fn parse<T: DeserializeOwned>(result: CallResult) -> Result<T, CallError> {
+    let value = result?;
+
+    serde_json::from_value(value).map_err(|err| CallError {
+        status: None,
+        reason: None,
+        message: format!("Failed to parse response: {}", err),
+        outcome_unknown: false,
+    })
+}
+
+async fn get_token(hub: &Hub) -> Result<String, Error> {
+    let scopes = [google_drive3::api::Scope::Full.as_ref()];
+
+    hub.auth
+        .get_token(&scopes)
+        .await
+        .map_err(|err| Error::Token(err.to_string()))?
+        .ok_or(Error::MissingToken)
+}
+
+async fn send_batch(
+    hub: &Hub,
+    token: &str,
+    calls: &[Call],
+    indexes: Vec<usize>,
+) -> Result<Vec<(usize, CallResult)>, Error> {
+    let body = build_body(calls, &indexes);
+
+    let request = hyper::Request::builder()
+        .method(http::Method::POST)
+        .uri(BATCH_URL)
+        .header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(
+            http::header::CONTENT_TYPE,
+            format!("multipart/mixed; boundary={}", BOUNDARY),
+        )
+        .body(hyper::Body::from(body))
+        .map_err(Error::BuildRequest)?;
+
+    // A failed batch request is reported as a failure of each call. Only the idempotent calls
+    // are retried, as drive may have executed the batch before the request failed
+    let response = match hub.client.request(request).await {
+        Ok(response) => response,
+        Err(err) => return Ok(fail_all(calls, &indexes, None, None, &err.to_string())),
+    };
+
+    let status = response.status();
+    let boundary = response
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_boundary);
+
+    let bytes = match hyper::body::to_bytes(response.into_body()).await {
+        Ok(bytes) => bytes,
+        Err(err) => return Ok(fail_all(calls, &indexes, None, None, &err.to_string())),
+    };
+
+    let body = String::from_utf8_lossy(&bytes);
+
+    let boundary = match boundary {
+        Some(boundary) if status.is_success() => boundary,
+        _ => {
+            let reason = error_reason(&body);
+            let message = error_message(&body);
+            return Ok(fail_all(
+                calls,
+                &indexes,
+                Some(status.as_u16()),
+                reason,
+                &message,
+            ));
+        }
+    };
+
+    let mut responses = parse_body(&body, &boundary);
+
+    let results = indexes
+        .into_iter()
+        .map(|index| {
+            let result = match responses.remove(&index) {
+                Some((status, body)) if (200..300).contains(&status) => {
+                    Ok(serde_json::from_str(&body).unwrap_or(serde_json::Value::Null))
+                }
+
+                Some((status, body)) => Err(CallError {
+                    status: Some(status),
+                    reason: error_reason(&body),
+                    message: error_message(&body),
+                    outcome_unknown: false,
+                }),
+
+                None => Err(CallError {
+                    status: None,
+                    reason: None,
+                    message: String::from("Missing response in batch"),
+                    outcome_unknown: true,
+                }),
+            };
+
+            (index, result)
+        })
+        .collect();
+
+    Ok(results)
+}
+
+fn build_body(calls: &[Call], indexes: &[usize]) -> String {
+    let mut body = String::new();
+
+    for index in indexes {
+        let call = &calls[*index];
+
+        body.push_str(&format!("--{}\r\n", BOUNDARY));
+        body.push_str("Content-Type: application/http\r\n");
+        body.push_str(&format!("Content-ID: <item-{}>\r\n\r\n", index));
+        body.push_str(&format!("{} {} HTTP/1.1\r\n", call.method, call.path));
+
+        match &call.body {
+            Some(json) => {
+                body.push_str("Content-Type: application/json; charset=UTF-8\r\n\r\n");
+                body.push_str(&json.to_string());
+                body.push_str("\r\n");
+            }
+
+            None => {
+                // fmt
+                body.push_str("\r\n");
+            }
+        }
+    }
+
+    body.push_str(&format!("--{}--\r\n", BOUNDARY));
+    body
+}
+
+// Parses the multipart response into (status, body) keyed by the index of the call
+fn parse_body(body: &str, boundary: &str) -> HashMap<usize, (u16, String)> {
+    let mut responses = HashMap::new();
+    let delimiter = format!("--{}", boundary);
+
+    for part in body.split(&delimiter) {
+        if part.starts_with("--") {
+            break;
+        }
+
+        let (part_headers, http_response) = match split_headers(part.trim_start()) {
+            Some(split) => split,
+            None => continue,
+        };
+
+        let index = part_headers
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.trim()
+                    .eq_ignore_ascii_case("content-id")
+                    .then(|| value.trim())
+            })
+            .and_then(|value| {
+                value
+                    .trim_matches(|c| c == '<' || c == '>')
+                    .rsplit_once('-')
+            })
+            .and_then(|(_, index)| index.parse::<usize>().ok());
+
+        let status = http_response
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|status| status.parse::<u16>().ok());
+
+        let response_body = split_headers(http_response)
+            .map(|(_, body)| body.trim().to_string())
+            .unwrap_or_default();
+
+        if let (Some(index), Some(status)) = (index, status) {
+            responses.insert(index, (status, response_body));
+        }
+    }
+
+    responses
+}
+
+fn split_headers(s: &str) -> Option<(&str, &str)> {
+    s.split_once("\r\n\r\n").or_else(|| s.split_once("\n\n"))
+}
+
+fn parse_boundary(content_type: &str) -> Option<String> {
+    content_type
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"').to_string())
+}
+
+fn fail_all(
+    calls: &[Call],
+    indexes: &[usize],
+    status: Option<u16>,
+    reason: Option<String>,
+    message: &str,
+) -> Vec<(usize, CallResult)> {
+    indexes
+        .iter()
+        .map(|index| {
+            // A batch rejected with a client error was not executed
+            let outcome_unknown = status.is_none_or(|status| status >= 500);
+
+            let message = if !outcome_unknown || is_idempotent(&calls[*index].method) {
+                message.to_string()
+            } else {
+                format!(
+                    "Batch request failed, the call may or may not have been applied: {}",
+                    message
+                )
+            };
+
+            let err = CallError {
+                status,
+                reason: reason.clone(),
+                message,
+                outcome_unknown,
+            };
+
+            (*index, Err(err))
+        })
+        .collect()
+}
+
+// Extracts the error message from a json error response
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| json["error"]["message"].as_str().map(|s| s.to_string()))
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+fn error_reason(body: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| {
+            json["error"]["errors"][0]["reason"]
+                .as_str()
+                .map(|s| s.to_string())
+        })
+}
+
+fn files_path(segments: &[&str], query: &[(&str, &str)]) -> String {
+    let mut path = String::from("/drive/v3/files");
+
+    for segment in segments {
+        path.push('/');
+        path.push_str(&encode(segment));
+    }
+
+    path.push_str("?supportsAllDrives=true");
+
+    for (name, value) in query {
+        path.push_str(&format!("&{}={}", name, encode(value)));
+    }
+
+    path
+}
+
+// Percent-encodes everything except the unreserved characters of RFC 3986
+fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Token(String),
+    MissingToken,
+    BuildRequest(http::Error),
+    SerializeBody(serde_json::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Token(err) => write!(f, "Failed to get access token: {}", err),
+            Error::MissingToken => write!(f, "Failed to get access token"),
+            Error::BuildRequest(err) => write!(f, "Failed to build request: {}", err),
+            Error::SerializeBody(err) => write!(f, "Failed to serialize request body: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_boundary_without_quotes() {
+        let boundary = parse_boundary("multipart/mixed; boundary=batch_abc123");
+        assert_eq!(boundary.as_deref(), Some("batch_abc123"));
+    }
+
+    #[test]
+    fn parse_boundary_in_quotes() {
+        let boundary = parse_boundary("multipart/mixed; charset=UTF-8; boundary=\"batch_abc123\"");
+        assert_eq!(boundary.as_deref(), Some("batch_abc123"));
+    }
+
+    #[test]
+    fn parse_boundary_missing() {
+        assert_eq!(parse_boundary("application/json; charset=UTF-8"), None);
+    }
+
+    #[test]
+    fn parse_body_with_crlf() {
+        let body = "--batch_abc\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: <response-item-0>\r\n\
+            \r\n\
+            HTTP/1.1 200 OK\r\n\
+            Content-Type: application/json; charset=UTF-8\r\n\
+            \r\n\
+            {\"id\": \"file1\"}\r\n\
+            --batch_abc\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: <response-item-1>\r\n\
+            \r\n\
+            HTTP/1.1 404 Not Found\r\n\
+            Content-Type: application/json; charset=UTF-8\r\n\
+            \r\n\
+            {\"error\": {\"code\": 404, \"message\": \"File not found: file2.\"}}\r\n\
+            --batch_abc--\r\n";
+
+        let responses = parse_body(body, "batch_abc");
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[&0], (200, String::from("{\"id\": \"file1\"}")));
+        assert_eq!(responses[&1].0, 404);
+        assert_eq!(error_message(&responses[&1].1), "File not found: file2.");
+    }
+
+    #[test]
+    fn parse_body_with_lf() {
+        let body = "--batch_abc\n\
+            Content-Type: application/http\n\
+            Content-ID: <response-item-3>\n\
+            \n\
+            HTTP/1.1 200 OK\n\
+            Content-Type: application/json; charset=UTF-8\n\
+            \n\
+            {\"id\": \"file1\"}\n\
+            --batch_abc--\n";
+
+        let responses = parse_body(body, "batch_abc");
+
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[&3], (200, String::from("{\"id\": \"file1\"}")));
+    }
+
+    #[test]
+    fn parse_body_with_empty_response_body() {
+        let body = "--batch_abc\r\n\
+            Content-Type: application/http\r\n\
+            Content-ID: <response-item-0>\r\n\
+            \r\n\
+            HTTP/1.1 204 No Content\r\n\
+            \r\n\
+            \r\n\
+            --batch_abc--\r\n";
+
+        let responses = parse_body(body, "batch_abc");
+
+        assert_eq!(responses[&0], (204, String::new()));
+    }
+
+    #[test]
+    fn parse_body_with_parts_out_of_order() {
+        let part = |index: usize, id: &str| {
+            format!(
+                "--batch_abc\r\n\
+                Content-Type: application/http\r\n\
+                Content-ID: <response-item-{}>\r\n\
+                \r\n\
+                HTTP/1.1 200 OK\r\n\
+                \r\n\
+                {{\"id\": \"{}\"}}\r\n",
+                index, id
+            )
+        };
+
+        let body = format!(
+            "{}{}{}--batch_abc--\r\n",
+            part(2, "c"),
+            part(0, "a"),
+            part(1, "b")
+        );
+
+        let responses = parse_body(&body, "batch_abc");
+
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[&0].1, "{\"id\": \"a\"}");
+        assert_eq!(responses[&1].1, "{\"id\": \"b\"}");
+        assert_eq!(responses[&2].1, "{\"id\": \"c\"}");
+    }
+
+    #[test]
+    fn parse_body_skips_parts_without_content_id() {
+        let body = "--batch_abc\r\n\
+            Content-Type: application/http\r\n\
+            \r\n\
+            HTTP/1.1 200 OK\r\n\
+            \r\n\
+            {}\r\n\
+            --batch_abc--\r\n";
+
+        assert!(parse_body(body, "batch_abc").is_empty());
+    }
+}
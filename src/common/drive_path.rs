@@ -0,0 +1,75 @@
+use crate::hub::Hub;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+struct PathNode {
+    name: String,
+    parent: Option<String>,
+}
+
+// Resolves the full path of files by following their parents. Folders are
+// only fetched once, which keeps the number of requests low for files in the same folder
+#[derive(Debug, Default)]
+pub struct DrivePathResolver {
+    nodes: HashMap<String, PathNode>,
+}
+
+impl DrivePathResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn path(&mut self, hub: &Hub, file: &google_drive3::api::File) -> String {
+        let mut names = vec![file.name.clone().unwrap_or_default()];
+        let mut parent = first_parent(file);
+
+        while let Some(parent_id) = parent {
+            // The parents of files that are shared with us are often not accessible,
+            // the path then starts at the topmost folder we can see
+            let node = match self.get_node(hub, &parent_id).await {
+                Ok(node) => node,
+                Err(_) => break,
+            };
+
+            names.push(node.name);
+            parent = node.parent;
+        }
+
+        names.reverse();
+        names.join("/")
+    }
+
+    async fn get_node(
+        &mut self,
+        hub: &Hub,
+        file_id: &str,
+    ) -> Result<PathNode, google_drive3::Error> {
+        if let Some(node) = self.nodes.get(file_id) {
+            return Ok(node.clone());
+        }
+
+        let (_, file) = hub
+            .files()
+            .get(file_id)
+            .param("fields", "id,name,parents")
+            .supports_all_drives(true)
+            .add_scope(google_drive3::api::Scope::Full)
+            .doit()
+            .await?;
+
+        let node = PathNode {
+            name: file.name.clone().unwrap_or_default(),
+            parent: first_parent(&file),
+        };
+
+        self.nodes.insert(file_id.to_string(), node.clone());
+
+        Ok(node)
+    }
+}
+
+fn first_parent(file: &google_drive3::api::File) -> Option<String> {
+    file.parents
+        .as_ref()
+        .and_then(|parents| parents.first().cloned())
+}
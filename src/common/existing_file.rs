@@ -0,0 +1,119 @@
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum ExistingFileAction {
+    #[default]
+    Abort,
+    Overwrite,
+    Skip,
+    Rename,
+    IfNewer,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Resolution {
+    Write(PathBuf),
+    Skip,
+    Abort,
+}
+
+impl ExistingFileAction {
+    // Decides what to do with a local path that is about to be written.
+    // The returned path may differ from the given path when renaming.
+    pub fn resolve(
+        &self,
+        path: &PathBuf,
+        remote_modified_time: Option<DateTime<chrono::Utc>>,
+    ) -> Resolution {
+        if !path.exists() {
+            return Resolution::Write(path.clone());
+        }
+
+        match self {
+            ExistingFileAction::Abort => Resolution::Abort,
+            ExistingFileAction::Overwrite => Resolution::Write(path.clone()),
+            ExistingFileAction::Skip => Resolution::Skip,
+            ExistingFileAction::Rename => Resolution::Write(next_available_path(path)),
+            ExistingFileAction::IfNewer => {
+                if is_remote_newer(path, remote_modified_time) {
+                    Resolution::Write(path.clone())
+                } else {
+                    Resolution::Skip
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ExistingFileAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExistingFileAction::Abort => write!(f, "abort"),
+            ExistingFileAction::Overwrite => write!(f, "overwrite"),
+            ExistingFileAction::Skip => write!(f, "skip"),
+            ExistingFileAction::Rename => write!(f, "rename"),
+            ExistingFileAction::IfNewer => write!(f, "if-newer"),
+        }
+    }
+}
+
+impl FromStr for ExistingFileAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "abort" => Ok(ExistingFileAction::Abort),
+            "overwrite" => Ok(ExistingFileAction::Overwrite),
+            "skip" => Ok(ExistingFileAction::Skip),
+            "rename" => Ok(ExistingFileAction::Rename),
+            "if-newer" => Ok(ExistingFileAction::IfNewer),
+            _ => Err(format!(
+                "'{}' is not a valid action, valid actions are: abort, overwrite, skip, rename, if-newer",
+                s
+            )),
+        }
+    }
+}
+
+// Finds the first path of the form 'name (n).ext' that does not exist
+fn next_available_path(path: &PathBuf) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let extension = path
+        .extension()
+        .map(|s| format!(".{}", s.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut n = 1;
+
+    loop {
+        let candidate = path.with_file_name(format!("{} ({}){}", stem, n, extension));
+
+        if !candidate.exists() {
+            return candidate;
+        }
+
+        n += 1;
+    }
+}
+
+fn is_remote_newer(path: &PathBuf, remote_modified_time: Option<DateTime<chrono::Utc>>) -> bool {
+    let local_modified_time = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::<chrono::Utc>::from);
+
+    match (remote_modified_time, local_modified_time) {
+        (Some(remote), Ok(local)) => remote > local,
+
+        // Without both timestamps we can't tell, so err on the side of downloading
+        _ => true,
+    }
+}
@@ -0,0 +1,91 @@
+use crate::common::table;
+use crate::common::table::Table;
+use serde::Serialize;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// Exit code used when some, but not necessarily all, items of a recursive operation failed
+pub const PARTIAL_FAILURE_EXIT_CODE: i32 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Failure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FailureReport {
+    failures: Vec<Failure>,
+}
+
+impl FailureReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, path: &PathBuf, err: &impl Display) {
+        eprintln!("Warning: Failed '{}': {}", path.display(), err);
+
+        self.failures.push(Failure {
+            path: path.display().to_string(),
+            error: err.to_string(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.failures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn print_summary(&self) {
+        let values: Vec<[String; 2]> = self
+            .failures
+            .iter()
+            .map(|failure| [failure.path.clone(), failure.error.clone()])
+            .collect();
+
+        let table = Table {
+            header: ["Path", "Error"],
+            values,
+        };
+
+        eprintln!();
+        eprintln!("{} items failed:", self.failures.len());
+
+        let _ = table::write(io::stderr(), table, &table::DisplayConfig::default());
+    }
+
+    pub fn save(&self, path: &PathBuf) -> Result<(), Error> {
+        let content = serde_json::to_string_pretty(&self.failures).map_err(Error::Serialize)?;
+        fs::write(path, content).map_err(|err| Error::Write(path.clone(), err))
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Serialize(serde_json::Error),
+    Write(PathBuf, io::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Serialize(err) => write!(f, "Failed to serialize failure report: {}", err),
+            Error::Write(path, err) => write!(
+                f,
+                "Failed to write failure report '{}': {}",
+                path.display(),
+                err
+            ),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -9,6 +11,8 @@ pub struct FileInfo {
     pub mime_type: mime::Mime,
     pub parents: Option<Vec<String>>,
     pub size: u64,
+    pub modified_time: Option<DateTime<chrono::Utc>>,
+    pub created_time: Option<DateTime<chrono::Utc>>,
 }
 
 pub struct Config {
@@ -25,7 +29,8 @@ impl FileInfo {
             .map(|s| s.to_string_lossy().to_string())
             .ok_or(Error::InvalidFilePath(config.file_path.clone()))?;
 
-        let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let metadata = file.metadata().ok();
+        let file_size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
 
         let mime_type = config.mime_type.clone().unwrap_or_else(|| {
             mime_guess::from_path(&config.file_path)
@@ -38,10 +43,22 @@ impl FileInfo {
             mime_type,
             parents: config.parents.clone(),
             size: file_size,
+            modified_time: metadata.as_ref().and_then(modified_time),
+            // Only sent on request, drive uses the upload time by default
+            created_time: None,
         })
     }
 }
 
+// Not all platforms and filesystems support these timestamps, in which case drive will use the upload time
+pub fn modified_time(metadata: &fs::Metadata) -> Option<DateTime<chrono::Utc>> {
+    metadata.modified().ok().map(DateTime::from)
+}
+
+pub fn created_time(metadata: &fs::Metadata) -> Option<DateTime<chrono::Utc>> {
+    metadata.created().ok().map(DateTime::from)
+}
+
 #[derive(Debug)]
 pub enum Error {
     InvalidFilePath(PathBuf),
@@ -0,0 +1,265 @@
+use crate::common::drive_file;
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::NaiveDate;
+use google_drive3::chrono::SecondsFormat;
+use std::fmt;
+use std::str::FromStr;
+
+// Prefix shared by the mime types of google documents, folders and shortcuts
+const MIME_TYPE_DRIVE_PREFIX: &str = "application/vnd.google-apps.";
+
+// Search query for files, compiled into clauses of the drive query language.
+// See https://developers.google.com/drive/api/guides/search-files
+#[derive(Debug, Clone, Default)]
+pub struct FileQuery {
+    pub parent: Option<String>,
+    pub name: Option<String>,
+    pub name_contains: Option<String>,
+    pub mime_type: Option<String>,
+    pub file_type: Option<FileTypeFilter>,
+    pub modified_after: Option<QueryDateTime>,
+    pub modified_before: Option<QueryDateTime>,
+    pub owner: Option<String>,
+    pub shared_with_me: bool,
+    pub starred: bool,
+    pub trashed: bool,
+    pub full_text: Option<String>,
+    pub custom: Option<String>,
+}
+
+impl FileQuery {
+    // True if any of the filters are set, the parent and custom query are not considered filters
+    pub fn has_filters(&self) -> bool {
+        self.name.is_some()
+            || self.name_contains.is_some()
+            || self.mime_type.is_some()
+            || self.file_type.is_some()
+            || self.modified_after.is_some()
+            || self.modified_before.is_some()
+            || self.owner.is_some()
+            || self.shared_with_me
+            || self.starred
+            || self.trashed
+            || self.full_text.is_some()
+    }
+
+    fn clauses(&self) -> Vec<String> {
+        let mut clauses = vec![];
+
+        if let Some(parent) = &self.parent {
+            clauses.push(parent_clause(parent));
+        }
+
+        if let Some(name) = &self.name {
+            clauses.push(format!("name = '{}'", escape(name)));
+        }
+
+        if let Some(name) = &self.name_contains {
+            clauses.push(format!("name contains '{}'", escape(name)));
+        }
+
+        if let Some(mime_type) = &self.mime_type {
+            clauses.push(format!("mimeType = '{}'", escape(mime_type)));
+        }
+
+        if let Some(file_type) = &self.file_type {
+            clauses.push(file_type.clause());
+        }
+
+        if let Some(time) = &self.modified_after {
+            clauses.push(format!("modifiedTime > '{}'", time));
+        }
+
+        if let Some(time) = &self.modified_before {
+            clauses.push(format!("modifiedTime < '{}'", time));
+        }
+
+        if let Some(owner) = &self.owner {
+            clauses.push(format!("'{}' in owners", escape(owner)));
+        }
+
+        if self.shared_with_me {
+            clauses.push(String::from("sharedWithMe = true"));
+        }
+
+        if self.starred {
+            clauses.push(String::from("starred = true"));
+        }
+
+        if let Some(text) = &self.full_text {
+            clauses.push(format!("fullText contains '{}'", escape(text)));
+        }
+
+        if let Some(custom) = &self.custom {
+            clauses.push(format!("({})", custom));
+        }
+
+        clauses.push(format!("trashed = {}", self.trashed));
+
+        clauses
+    }
+}
+
+impl fmt::Display for FileQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.clauses().join(" and "))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FileTypeFilter {
+    Folder,
+    Document,
+    Regular,
+    Shortcut,
+}
+
+impl FileTypeFilter {
+    // Type of the file, the types partition all files
+    pub fn of(file: &google_drive3::api::File) -> FileTypeFilter {
+        if drive_file::is_directory(file) {
+            FileTypeFilter::Folder
+        } else if drive_file::is_binary(file) {
+            FileTypeFilter::Regular
+        } else if drive_file::is_shortcut(file) {
+            FileTypeFilter::Shortcut
+        } else {
+            FileTypeFilter::Document
+        }
+    }
+
+    fn clause(&self) -> String {
+        match self {
+            FileTypeFilter::Folder => {
+                format!("mimeType = '{}'", drive_file::MIME_TYPE_DRIVE_FOLDER)
+            }
+
+            FileTypeFilter::Shortcut => {
+                format!("mimeType = '{}'", drive_file::MIME_TYPE_DRIVE_SHORTCUT)
+            }
+
+            FileTypeFilter::Document => format!(
+                "mimeType contains '{}' and mimeType != '{}' and mimeType != '{}'",
+                MIME_TYPE_DRIVE_PREFIX,
+                drive_file::MIME_TYPE_DRIVE_FOLDER,
+                drive_file::MIME_TYPE_DRIVE_SHORTCUT
+            ),
+
+            FileTypeFilter::Regular => {
+                format!("not mimeType contains '{}'", MIME_TYPE_DRIVE_PREFIX)
+            }
+        }
+    }
+}
+
+impl fmt::Display for FileTypeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileTypeFilter::Folder => write!(f, "folder"),
+            FileTypeFilter::Document => write!(f, "document"),
+            FileTypeFilter::Regular => write!(f, "regular"),
+            FileTypeFilter::Shortcut => write!(f, "shortcut"),
+        }
+    }
+}
+
+impl FromStr for FileTypeFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "folder" => Ok(FileTypeFilter::Folder),
+            "document" => Ok(FileTypeFilter::Document),
+            "regular" => Ok(FileTypeFilter::Regular),
+            "shortcut" => Ok(FileTypeFilter::Shortcut),
+            _ => Err(format!(
+                "'{}' is not a valid type, valid types are: folder, document, regular, shortcut",
+                s
+            )),
+        }
+    }
+}
+
+// Point in time accepted as either a RFC 3339 timestamp or a date (midnight UTC)
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct QueryDateTime(pub DateTime<chrono::Utc>);
+
+impl fmt::Display for QueryDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+impl FromStr for QueryDateTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+            return Ok(QueryDateTime(time.with_timezone(&chrono::Utc)));
+        }
+
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|time| QueryDateTime(DateTime::from_utc(time, chrono::Utc)))
+            .ok_or(format!(
+                "'{}' is not a valid time, use a date like 2023-01-31 or a RFC 3339 timestamp like 2023-01-31T12:00:00Z",
+                s
+            ))
+    }
+}
+
+// Escapes a value for use inside a single quoted string in a drive query
+pub fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+// Matches the files directly in the folder or drive with the given id
+pub fn parent_clause(parent_id: &str) -> String {
+    format!("'{}' in parents", escape(parent_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_quotes_and_backslashes() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("it's"), "it\\'s");
+        assert_eq!(escape("a\\b"), "a\\\\b");
+        assert_eq!(escape("\\'"), "\\\\\\'");
+    }
+
+    #[test]
+    fn parent_clause_escapes_the_id() {
+        assert_eq!(parent_clause("abc123"), "'abc123' in parents");
+        assert_eq!(
+            parent_clause("x' or 'a' = 'a"),
+            "'x\\' or \\'a\\' = \\'a' in parents"
+        );
+    }
+
+    #[test]
+    fn query_joins_escaped_clauses() {
+        let query = FileQuery {
+            parent: Some(String::from("folder'id")),
+            name: Some(String::from("O'Brien.txt")),
+            custom: Some(String::from(
+                "starred = true or viewedByMeTime > '2023-01-01'",
+            )),
+            ..FileQuery::default()
+        };
+
+        assert_eq!(
+            query.to_string(),
+            "'folder\\'id' in parents and name = 'O\\'Brien.txt' and (starred = true or viewedByMeTime > '2023-01-01') and trashed = false"
+        );
+    }
+
+    #[test]
+    fn query_without_filters_only_excludes_trashed_files() {
+        assert_eq!(FileQuery::default().to_string(), "trashed = false");
+    }
+}
@@ -1,13 +1,18 @@
+use crate::common::file_info;
 use crate::common::file_info::FileInfo;
 use crate::common::id_gen;
 use crate::common::id_gen::IdGen;
 use async_recursion::async_recursion;
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
 use std::error;
+use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fs;
 use std::io;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 #[derive(Debug, Clone)]
 pub struct FileTree {
@@ -15,15 +20,41 @@ pub struct FileTree {
 }
 
 impl FileTree {
-    pub async fn from_path<'a>(path: &PathBuf, ids: &mut IdGen<'a>) -> Result<FileTree, Error> {
+    pub async fn from_path<'a>(
+        path: &PathBuf,
+        symlinks: SymlinkAction,
+        ids: &mut IdGen<'a>,
+    ) -> Result<FileTree, Error> {
         let canonical_path = path
             .canonicalize()
             .map_err(|err| Error::CanonicalizePath(path.clone(), err))?;
 
-        let root = Folder::from_path(&canonical_path, None, ids).await?;
+        let root = Folder::from_path(&canonical_path, None, symlinks, ids).await?;
         Ok(FileTree { root })
     }
 
+    pub fn shortcuts(&self) -> Vec<Shortcut> {
+        self.folders()
+            .iter()
+            .flat_map(|folder| folder.shortcuts())
+            .collect()
+    }
+
+    // Finds the drive id of the folder or file at the given canonical path
+    pub fn find_drive_id(&self, path: &PathBuf) -> Option<String> {
+        self.folders().iter().find_map(|folder| {
+            if &folder.path == path {
+                Some(folder.drive_id.clone())
+            } else {
+                folder
+                    .files()
+                    .iter()
+                    .find(|file| &file.path == path)
+                    .map(|file| file.drive_id.clone())
+            }
+        })
+    }
+
     pub fn folders(&self) -> Vec<Folder> {
         let mut folders = vec![];
 
@@ -78,6 +109,44 @@ pub struct TreeInfo {
 pub enum Node {
     FolderNode(Folder),
     FileNode(File),
+    ShortcutNode(Shortcut),
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum SymlinkAction {
+    #[default]
+    Follow,
+    Skip,
+    Shortcut,
+    Error,
+}
+
+impl fmt::Display for SymlinkAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymlinkAction::Follow => write!(f, "follow"),
+            SymlinkAction::Skip => write!(f, "skip"),
+            SymlinkAction::Shortcut => write!(f, "shortcut"),
+            SymlinkAction::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl FromStr for SymlinkAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "follow" => Ok(SymlinkAction::Follow),
+            "skip" => Ok(SymlinkAction::Skip),
+            "shortcut" => Ok(SymlinkAction::Shortcut),
+            "error" => Ok(SymlinkAction::Error),
+            _ => Err(format!(
+                "'{}' is not a valid symlink action, valid actions are: follow, skip, shortcut, error",
+                s
+            )),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -94,6 +163,7 @@ impl Folder {
     pub async fn from_path<'a>(
         path: &PathBuf,
         parent: Option<&'async_recursion Folder>,
+        symlinks: SymlinkAction,
         ids: &mut IdGen<'a>,
     ) -> Result<Folder, Error> {
         let name = path
@@ -101,7 +171,7 @@ impl Folder {
             .map(|s| s.to_string_lossy().to_string())
             .ok_or(Error::InvalidPath(path.clone()))?;
 
-        let drive_id = ids.next().await.map_err(Error::GetId)?;
+        let drive_id = ids.id_for(path).await.map_err(Error::GetId)?;
 
         let mut folder = Folder {
             name,
@@ -117,19 +187,58 @@ impl Folder {
         for e in entries {
             let entry = e.map_err(Error::ReadDirEntry)?;
             let path = entry.path();
+            let file_type = entry.file_type().map_err(Error::ReadDirEntry)?;
+
+            if file_type.is_symlink() {
+                match symlinks {
+                    SymlinkAction::Error => {
+                        // fmt
+                        return Err(Error::IsSymlink(path.clone()));
+                    }
+
+                    SymlinkAction::Skip => {
+                        eprintln!("Warning: Skipping symlink '{}'", path.display());
+                        continue;
+                    }
+
+                    SymlinkAction::Shortcut => {
+                        match Shortcut::from_path(&path, &folder, ids).await? {
+                            Some(shortcut) => children.push(Node::ShortcutNode(shortcut)),
+                            None => {
+                                eprintln!("Warning: Skipping broken symlink '{}'", path.display())
+                            }
+                        }
+                        continue;
+                    }
+
+                    SymlinkAction::Follow => {
+                        if !path.exists() {
+                            eprintln!("Warning: Skipping broken symlink '{}'", path.display());
+                            continue;
+                        }
+
+                        if path.is_dir() && folder.is_self_or_ancestor(&path) {
+                            eprintln!(
+                                "Warning: Skipping symlink '{}', following it would create a loop",
+                                path.display()
+                            );
+                            continue;
+                        }
+                    }
+                }
+            }
 
             if path.is_dir() {
-                let folder = Folder::from_path(&path, Some(&folder), ids).await?;
+                let folder = Folder::from_path(&path, Some(&folder), symlinks, ids).await?;
                 let node = Node::FolderNode(folder);
                 children.push(node);
             } else if path.is_file() {
                 let file = File::from_path(&path, &folder, ids).await?;
                 let node = Node::FileNode(file);
                 children.push(node);
-            } else if path.is_file() {
-                return Err(Error::IsSymlink(path.clone()));
             } else {
-                return Err(Error::UnknownFileType(path.clone()));
+                // Sockets, fifos, devices, etc
+                eprintln!("Warning: Skipping special file '{}'", path.display());
             }
         }
 
@@ -152,12 +261,46 @@ impl Folder {
         files
     }
 
+    pub fn shortcuts(&self) -> Vec<Shortcut> {
+        let mut shortcuts = vec![];
+
+        for child in &self.children {
+            if let Node::ShortcutNode(shortcut) = child {
+                shortcuts.push(shortcut.clone());
+            }
+        }
+
+        shortcuts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        shortcuts
+    }
+
     pub fn relative_path(&self) -> PathBuf {
         let mut root_path = get_root_folder(self).path;
         root_path.pop();
         self.path.strip_prefix(root_path).unwrap().to_path_buf()
     }
 
+    // Checks if the given directory resolves to this folder or one of its ancestors
+    pub fn is_self_or_ancestor(&self, path: &PathBuf) -> bool {
+        let canonical_path = match path.canonicalize() {
+            Ok(path) => path,
+            Err(_) => return false,
+        };
+
+        let mut candidate = Some(self.clone());
+
+        while let Some(folder) = candidate {
+            if folder.path.canonicalize().ok().as_ref() == Some(&canonical_path) {
+                return true;
+            }
+
+            candidate = folder.parent.map(|folder| *folder);
+        }
+
+        false
+    }
+
     pub fn folders_recursive(&self) -> Vec<Folder> {
         Folder::collect_folders_recursive(&self)
     }
@@ -197,6 +340,8 @@ pub struct File {
     pub mime_type: mime::Mime,
     pub parent: Folder,
     pub drive_id: String,
+    pub modified_time: Option<DateTime<chrono::Utc>>,
+    pub created_time: Option<DateTime<chrono::Utc>>,
 }
 
 impl File {
@@ -211,11 +356,12 @@ impl File {
             .ok_or(Error::InvalidPath(path.clone()))?;
 
         let os_file = fs::File::open(path).map_err(|err| Error::OpenFile(path.clone(), err))?;
-        let size = os_file.metadata().map(|m| m.len()).unwrap_or(0);
+        let metadata = os_file.metadata().ok();
+        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
         let mime_type = mime_guess::from_path(path)
             .first()
             .unwrap_or(mime::APPLICATION_OCTET_STREAM);
-        let drive_id = ids.next().await.map_err(Error::GetId)?;
+        let drive_id = ids.id_for(path).await.map_err(Error::GetId)?;
 
         let file = File {
             name,
@@ -224,6 +370,8 @@ impl File {
             mime_type,
             parent: parent.clone(),
             drive_id,
+            modified_time: metadata.as_ref().and_then(file_info::modified_time),
+            created_time: metadata.as_ref().and_then(file_info::created_time),
         };
 
         Ok(file)
@@ -241,10 +389,56 @@ impl File {
             size: self.size,
             mime_type: self.mime_type.clone(),
             parents,
+            modified_time: self.modified_time,
+            created_time: self.created_time,
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct Shortcut {
+    pub name: String,
+    pub path: PathBuf,
+    pub target: PathBuf,
+    pub parent: Folder,
+    pub drive_id: String,
+}
+
+impl Shortcut {
+    // Returns None if the symlink target does not exist
+    pub async fn from_path<'a>(
+        path: &PathBuf,
+        parent: &Folder,
+        ids: &mut IdGen<'a>,
+    ) -> Result<Option<Shortcut>, Error> {
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => return Ok(None),
+        };
+
+        let target = match path.canonicalize() {
+            Ok(target) => target,
+            Err(_) => return Ok(None),
+        };
+
+        let drive_id = ids.id_for(path).await.map_err(Error::GetId)?;
+
+        Ok(Some(Shortcut {
+            name,
+            path: path.clone(),
+            target,
+            parent: parent.clone(),
+            drive_id,
+        }))
+    }
+
+    pub fn relative_path(&self) -> PathBuf {
+        let mut root_path = get_root_folder(&self.parent).path;
+        root_path.pop();
+        self.path.strip_prefix(root_path).unwrap().to_path_buf()
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     CanonicalizePath(PathBuf, io::Error),
@@ -254,7 +448,6 @@ pub enum Error {
     GetId(id_gen::Error),
     InvalidPath(PathBuf),
     IsSymlink(PathBuf),
-    UnknownFileType(PathBuf),
 }
 
 impl error::Error for Error {}
@@ -275,8 +468,11 @@ impl Display for Error {
             }
             Error::GetId(e) => write!(f, "Error getting id: {}", e),
             Error::InvalidPath(path) => write!(f, "Invalid path: {}", path.display()),
-            Error::IsSymlink(path) => write!(f, "Path is symlink: {}", path.display()),
-            Error::UnknownFileType(path) => write!(f, "Unknown file type: {}", path.display()),
+            Error::IsSymlink(path) => write!(
+                f,
+                "Path is symlink: {}, use --symlinks to choose how symlinks are handled",
+                path.display()
+            ),
         }
     }
 }
@@ -1,8 +1,14 @@
 use crate::common::drive_file;
+use crate::common::file_query::FileTypeFilter;
+use crate::common::local_name;
+use crate::common::local_name::ManifestEntry;
+use crate::common::local_name::ManifestEntryKind;
 use crate::files::list;
 use crate::files::list::ListQuery;
 use crate::hub::Hub;
 use async_recursion::async_recursion;
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -13,15 +19,71 @@ pub struct FileTreeDrive {
     pub root: Folder,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct TreeOptions {
+    // Include google documents and shortcuts, which can't be downloaded as is
+    pub include_documents: bool,
+
+    // Max depth of nodes below the root folder, unlimited if not set
+    pub max_depth: Option<usize>,
+}
+
 impl FileTreeDrive {
     pub async fn from_file(
         hub: &Hub,
         file: &google_drive3::api::File,
     ) -> Result<FileTreeDrive, Error> {
-        let root = Folder::from_file(hub, file, None).await?;
+        FileTreeDrive::from_file_with_options(hub, file, &TreeOptions::default()).await
+    }
+
+    pub async fn from_file_with_options(
+        hub: &Hub,
+        file: &google_drive3::api::File,
+        options: &TreeOptions,
+    ) -> Result<FileTreeDrive, Error> {
+        let name = file.name.clone().ok_or(Error::MissingFileName)?;
+        let local_name = local_name::sanitize(&name);
+        let root = Folder::from_file(hub, file, local_name, None, options).await?;
         Ok(FileTreeDrive { root })
     }
 
+    pub fn manifest(&self) -> Vec<ManifestEntry> {
+        self.collect_manifest(|_, _| true)
+    }
+
+    // Folders and files whose local name differs from the name on drive
+    pub fn renamed_entries(&self) -> Vec<ManifestEntry> {
+        self.collect_manifest(|name, local_name| name != local_name)
+    }
+
+    fn collect_manifest<F: Fn(&str, &str) -> bool>(&self, include: F) -> Vec<ManifestEntry> {
+        let mut entries = vec![];
+
+        for folder in self.folders() {
+            if include(&folder.name, &folder.local_name) {
+                entries.push(ManifestEntry {
+                    id: folder.drive_id.clone(),
+                    kind: ManifestEntryKind::Folder,
+                    drive_name: folder.name.clone(),
+                    local_path: folder.relative_path().to_string_lossy().to_string(),
+                });
+            }
+
+            for file in folder.files() {
+                if include(&file.name, &file.local_name) {
+                    entries.push(ManifestEntry {
+                        id: file.drive_id.clone(),
+                        kind: ManifestEntryKind::File,
+                        drive_name: file.name.clone(),
+                        local_path: file.relative_path().to_string_lossy().to_string(),
+                    });
+                }
+            }
+        }
+
+        entries
+    }
+
     pub fn folders(&self) -> Vec<Folder> {
         let mut folders = vec![];
 
@@ -34,7 +96,7 @@ impl FileTreeDrive {
             let parent_count_b = b.ancestor_count();
 
             if parent_count_a == parent_count_b {
-                a.name.cmp(&b.name)
+                a.local_name.cmp(&b.local_name)
             } else {
                 parent_count_a.cmp(&parent_count_b)
             }
@@ -78,12 +140,23 @@ pub enum Node {
     FileNode(File),
 }
 
+impl Node {
+    pub fn name(&self) -> &str {
+        match self {
+            Node::FolderNode(folder) => &folder.name,
+            Node::FileNode(file) => &file.name,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Folder {
     pub name: String,
+    pub local_name: String,
     pub parent: Option<Box<Folder>>,
     pub children: Vec<Node>,
     pub drive_id: String,
+    pub modified_time: Option<DateTime<chrono::Utc>>,
 }
 
 impl Folder {
@@ -91,7 +164,9 @@ impl Folder {
     pub async fn from_file(
         hub: &Hub,
         file: &google_drive3::api::File,
+        local_name: String,
         parent: Option<&'async_recursion Folder>,
+        options: &TreeOptions,
     ) -> Result<Folder, Error> {
         err_if_not_directory(&file)?;
 
@@ -100,35 +175,64 @@ impl Folder {
 
         let mut folder = Folder {
             name,
+            local_name,
             parent: parent.map(|folder| Box::new(folder.clone())),
             children: Vec::new(),
             drive_id: file_id.clone(),
+            modified_time: file.modified_time,
         };
 
+        let child_depth = folder.ancestor_count() + 1;
+        if options.max_depth.is_some_and(|max| child_depth > max) {
+            return Ok(folder);
+        }
+
         let files = list::list_files(
             hub,
             &list::ListFilesConfig {
                 query: ListQuery::FilesInFolder { folder_id: file_id },
                 order_by: Default::default(),
                 max_files: usize::MAX,
+                fields: None,
             },
         )
         .await
         .map_err(Error::ListFiles)?;
 
+        // Skip documents and shortcuts unless asked for
+        let files: Vec<google_drive3::api::File> = files
+            .into_iter()
+            .filter(|file| {
+                options.include_documents
+                    || drive_file::is_directory(file)
+                    || drive_file::is_binary(file)
+            })
+            .collect();
+
+        let local_names = local_name::assign(
+            &files
+                .iter()
+                .map(|file| {
+                    (
+                        file.name.clone().unwrap_or_default(),
+                        file.id.clone().unwrap_or_default(),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        );
+
         let mut children = Vec::new();
 
-        for file in files {
-            if drive_file::is_directory(&file) {
-                let folder = Folder::from_file(hub, &file, Some(&folder)).await?;
+        for (file, local_name) in files.iter().zip(local_names) {
+            if drive_file::is_directory(file) {
+                let folder =
+                    Folder::from_file(hub, file, local_name, Some(&folder), options).await?;
                 let node = Node::FolderNode(folder);
                 children.push(node);
-            } else if drive_file::is_binary(&file) {
-                let f = File::from_file(&file, &folder).await?;
+            } else {
+                let f = File::from_file(file, local_name, &folder).await?;
                 let node = Node::FileNode(f);
                 children.push(node);
-            } else {
-                // Skip documents
             }
         }
 
@@ -146,19 +250,26 @@ impl Folder {
             }
         }
 
-        files.sort_by(|a, b| a.name.cmp(&b.name));
+        files.sort_by(|a, b| a.local_name.cmp(&b.local_name));
 
         files
     }
 
+    // Child folders and files sorted by name
+    pub fn sorted_children(&self) -> Vec<Node> {
+        let mut children = self.children.clone();
+        children.sort_by(|a, b| a.name().cmp(b.name()));
+        children
+    }
+
     pub fn relative_path(&self) -> PathBuf {
         let mut path = PathBuf::new();
 
         for folder in get_ancestors(self) {
-            path.push(&folder.name);
+            path.push(&folder.local_name);
         }
 
-        path.join(&self.name)
+        path.join(&self.local_name)
     }
 
     pub fn folders_recursive(&self) -> Vec<Folder> {
@@ -195,35 +306,66 @@ impl Folder {
 #[derive(Debug, Clone)]
 pub struct File {
     pub name: String,
+    pub local_name: String,
     pub size: u64,
     pub parent: Folder,
     pub drive_id: String,
     pub md5: Option<String>,
+    pub modified_time: Option<DateTime<chrono::Utc>>,
+    pub file_type: FileTypeFilter,
+    pub mime_type: Option<String>,
+    pub owners: Vec<String>,
 }
 
 impl File {
     pub async fn from_file(
         file: &google_drive3::api::File,
+        local_name: String,
         parent: &Folder,
     ) -> Result<File, Error> {
         let name = file.name.clone().ok_or(Error::MissingFileName)?;
-        let size = file.size.ok_or(Error::MissingFileSize)? as u64;
         let file_id = file.id.clone().ok_or(Error::MissingFileId)?;
         let md5 = file.md5_checksum.clone();
+        let modified_time = file.modified_time;
+        let file_type = FileTypeFilter::of(file);
+        let mime_type = file.mime_type.clone();
+
+        let owners = file
+            .owners
+            .as_ref()
+            .map(|owners| {
+                owners
+                    .iter()
+                    .filter_map(|owner| owner.email_address.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Documents and shortcuts don't have a size
+        let size = if drive_file::is_binary(file) {
+            file.size.ok_or(Error::MissingFileSize)? as u64
+        } else {
+            file.size.unwrap_or_default() as u64
+        };
 
         let file = File {
             name,
+            local_name,
             size,
             parent: parent.clone(),
             drive_id: file_id,
             md5,
+            modified_time,
+            file_type,
+            mime_type,
+            owners,
         };
 
         Ok(file)
     }
 
     pub fn relative_path(&self) -> PathBuf {
-        self.parent.relative_path().join(&self.name)
+        self.parent.relative_path().join(&self.local_name)
     }
 }
 
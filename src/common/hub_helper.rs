@@ -20,6 +20,28 @@ pub async fn get_hub() -> Result<Hub, Error> {
     Ok(hub)
 }
 
+// Http status of a failed api call. Error responses with a json body are returned as bad
+// requests, whatever their status is
+pub fn error_status(err: &google_drive3::Error) -> Option<u16> {
+    match err {
+        google_drive3::Error::BadRequest(body) => body["error"]["code"]
+            .as_u64()
+            .and_then(|code| u16::try_from(code).ok()),
+        google_drive3::Error::Failure(res) => Some(res.status().as_u16()),
+        _ => None,
+    }
+}
+
+// Reason of the first error of a failed api call, like 'consentRequiredForOwnershipTransfer'
+pub fn error_reason(err: &google_drive3::Error) -> Option<String> {
+    match err {
+        google_drive3::Error::BadRequest(body) => body["error"]["errors"][0]["reason"]
+            .as_str()
+            .map(|reason| reason.to_string()),
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     AppConfig(app_config::Error),
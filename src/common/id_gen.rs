@@ -1,14 +1,17 @@
 use crate::common::delegate::UploadDelegateConfig;
 use crate::files::generate_ids;
 use crate::hub::Hub;
+use std::collections::HashMap;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::path::PathBuf;
 
 pub struct IdGen<'a> {
     hub: &'a Hub,
     delegate_config: UploadDelegateConfig,
     ids: Vec<String>,
+    reserved: HashMap<PathBuf, String>,
 }
 
 impl<'a> IdGen<'a> {
@@ -17,6 +20,19 @@ impl<'a> IdGen<'a> {
             hub,
             delegate_config: delegate_config.clone(),
             ids: Vec::new(),
+            reserved: HashMap::new(),
+        }
+    }
+
+    // Ids that were already assigned to paths, i.e. when resuming an upload
+    pub fn with_reserved_ids(self, reserved: HashMap<PathBuf, String>) -> Self {
+        Self { reserved, ..self }
+    }
+
+    pub async fn id_for(&mut self, path: &PathBuf) -> Result<String, Error> {
+        match self.reserved.get(path) {
+            Some(id) => Ok(id.clone()),
+            None => self.next().await,
         }
     }
 
@@ -0,0 +1,222 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+// Characters that are not allowed in file names on at least one of the supported platforms
+const UNSAFE_CHARS: &[char] = &['/', '\\', '<', '>', ':', '"', '|', '?', '*'];
+
+// Device names that windows reserves, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub id: String,
+    pub kind: ManifestEntryKind,
+    pub drive_name: String,
+    pub local_path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ManifestEntryKind {
+    Folder,
+    File,
+}
+
+// Escapes characters that would change the meaning of the name on the local filesystem
+// or that are rejected by it. Unsafe characters are percent-encoded
+pub fn sanitize(name: &str) -> String {
+    if name.is_empty() {
+        return String::from("_");
+    }
+
+    if name == "." || name == ".." {
+        return name.replace('.', "%2E");
+    }
+
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| {
+            if UNSAFE_CHARS.contains(&c) || c.is_control() {
+                percent_encode(c)
+            } else {
+                c.to_string()
+            }
+        })
+        .collect();
+
+    // Trailing dots and spaces are silently stripped on windows
+    let trimmed_len = sanitized.trim_end_matches(['.', ' ']).len();
+    let trailing: String = sanitized[trimmed_len..]
+        .chars()
+        .map(percent_encode)
+        .collect();
+    sanitized.truncate(trimmed_len);
+    sanitized.push_str(&trailing);
+
+    // Encoding the first character is enough to make a reserved name an ordinary one
+    if is_reserved(&sanitized) {
+        let first_len = sanitized.chars().next().map_or(0, char::len_utf8);
+        sanitized = format!(
+            "{}{}",
+            percent_encode_str(&sanitized[..first_len]),
+            &sanitized[first_len..]
+        );
+    }
+
+    sanitized
+}
+
+fn is_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default().trim_end();
+
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+// Appends the drive id to the name, before the extension if there is one
+pub fn disambiguate(name: &str, id: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => {
+            format!("{} [{}].{}", stem, id, extension)
+        }
+
+        _ => {
+            // fmt
+            format!("{} [{}]", name, id)
+        }
+    }
+}
+
+// Returns a local name for each (name, id) pair. Names that collide after sanitization
+// (case-insensitively, to be safe on macos and windows) are all disambiguated with their id
+pub fn assign(entries: &[(String, String)]) -> Vec<String> {
+    let sanitized: Vec<String> = entries.iter().map(|(name, _)| sanitize(name)).collect();
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for name in &sanitized {
+        *counts.entry(name.to_lowercase()).or_default() += 1;
+    }
+
+    sanitized
+        .into_iter()
+        .zip(entries)
+        .map(|(name, (_, id))| {
+            if counts.get(&name.to_lowercase()).copied().unwrap_or(0) > 1 {
+                disambiguate(&name, id)
+            } else {
+                name
+            }
+        })
+        .collect()
+}
+
+fn percent_encode_str(s: &str) -> String {
+    s.chars().map(percent_encode).collect()
+}
+
+fn percent_encode(c: char) -> String {
+    let mut buf = [0; 4];
+
+    c.encode_utf8(&mut buf)
+        .bytes()
+        .map(|b| format!("%{:02X}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(names: &[(&str, &str)]) -> Vec<(String, String)> {
+        names
+            .iter()
+            .map(|(name, id)| (name.to_string(), id.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn sanitize_keeps_safe_names() {
+        assert_eq!(sanitize("report.pdf"), "report.pdf");
+        assert_eq!(sanitize("Ünïcödé name"), "Ünïcödé name");
+    }
+
+    #[test]
+    fn sanitize_encodes_reserved_characters() {
+        assert_eq!(sanitize("a/b"), "a%2Fb");
+        assert_eq!(sanitize("a\\b"), "a%5Cb");
+        assert_eq!(sanitize("<>:\"|?*"), "%3C%3E%3A%22%7C%3F%2A");
+        assert_eq!(sanitize("tab\there"), "tab%09here");
+    }
+
+    #[test]
+    fn sanitize_encodes_dot_names_and_empty_names() {
+        assert_eq!(sanitize(""), "_");
+        assert_eq!(sanitize("."), "%2E");
+        assert_eq!(sanitize(".."), "%2E%2E");
+        assert_eq!(sanitize(".hidden"), ".hidden");
+    }
+
+    #[test]
+    fn sanitize_encodes_trailing_dots_and_spaces() {
+        assert_eq!(sanitize("name."), "name%2E");
+        assert_eq!(sanitize("name "), "name%20");
+        assert_eq!(sanitize("name. ."), "name%2E%20%2E");
+        assert_eq!(sanitize("a. b"), "a. b");
+    }
+
+    #[test]
+    fn sanitize_encodes_windows_reserved_names() {
+        assert_eq!(sanitize("CON"), "%43ON");
+        assert_eq!(sanitize("nul"), "%6Eul");
+        assert_eq!(sanitize("com1.txt"), "%63om1.txt");
+        assert_eq!(sanitize("LPT9.tar.gz"), "%4CPT9.tar.gz");
+        assert_eq!(sanitize("AUX .txt"), "%41UX .txt");
+    }
+
+    #[test]
+    fn sanitize_keeps_names_that_only_start_like_reserved_names() {
+        assert_eq!(sanitize("CONSOLE"), "CONSOLE");
+        assert_eq!(sanitize("COM10"), "COM10");
+        assert_eq!(sanitize("my CON"), "my CON");
+    }
+
+    #[test]
+    fn disambiguate_inserts_the_id_before_the_extension() {
+        assert_eq!(disambiguate("report.pdf", "abc"), "report [abc].pdf");
+        assert_eq!(
+            disambiguate("archive.tar.gz", "abc"),
+            "archive.tar [abc].gz"
+        );
+        assert_eq!(disambiguate("README", "abc"), "README [abc]");
+        assert_eq!(disambiguate(".hidden", "abc"), ".hidden [abc]");
+    }
+
+    #[test]
+    fn assign_keeps_unique_names() {
+        let names = assign(&entries(&[("a.txt", "1"), ("b.txt", "2")]));
+        assert_eq!(names, vec!["a.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn assign_disambiguates_all_duplicates() {
+        let names = assign(&entries(&[("a.txt", "1"), ("b.txt", "2"), ("a.txt", "3")]));
+        assert_eq!(names, vec!["a [1].txt", "b.txt", "a [3].txt"]);
+    }
+
+    #[test]
+    fn assign_disambiguates_names_that_only_differ_in_case() {
+        let names = assign(&entries(&[("Notes", "1"), ("notes", "2")]));
+        assert_eq!(names, vec!["Notes [1]", "notes [2]"]);
+    }
+
+    #[test]
+    fn assign_disambiguates_names_that_collide_after_sanitizing() {
+        let names = assign(&entries(&[("a/b", "1"), ("a%2Fb", "2")]));
+        assert_eq!(names, vec!["a%2Fb [1]", "a%2Fb [2]"]);
+    }
+}
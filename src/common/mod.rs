@@ -1,13 +1,21 @@
 pub mod account_archive;
+pub mod batch;
 pub mod delegate;
+pub mod drive_batch;
 pub mod drive_file;
+pub mod drive_path;
 pub mod empty_file;
+pub mod existing_file;
+pub mod failure_report;
 pub mod file_info;
+pub mod file_query;
 pub mod file_tree;
 pub mod file_tree_drive;
 pub mod hub_helper;
 pub mod id_gen;
+pub mod local_name;
 pub mod md5_writer;
 pub mod permission;
 pub mod table;
+pub mod upload_journal;
 pub mod file_helper;
@@ -1,3 +1,7 @@
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::SecondsFormat;
+use std::error;
 use std::fmt;
 use std::str::FromStr;
 
@@ -12,6 +16,19 @@ pub enum Role {
     Reader,
 }
 
+impl Role {
+    pub fn supports_expiration(&self) -> bool {
+        match self {
+            Role::Owner => false,
+            Role::Organizer => false,
+            Role::FileOrganizer => false,
+            Role::Writer => true,
+            Role::Commenter => true,
+            Role::Reader => true,
+        }
+    }
+}
+
 impl fmt::Display for Role {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -77,6 +94,30 @@ impl Type {
             Type::Anyone => true,
         }
     }
+
+    // Notification emails and expiration times are only supported for users and groups
+    pub fn supports_notification(&self) -> bool {
+        match self {
+            Type::User => true,
+            Type::Group => true,
+            Type::Domain => false,
+            Type::Anyone => false,
+        }
+    }
+
+    pub fn supports_expiration(&self) -> bool {
+        self.supports_notification()
+    }
+
+    // Ownership can only be transferred to a user, and shared drive
+    // organizer roles can't be granted to anyone
+    pub fn supports_role(&self, role: &Role) -> bool {
+        match role {
+            Role::Owner => self == &Type::User,
+            Role::Organizer | Role::FileOrganizer => self != &Type::Anyone,
+            _ => true,
+        }
+    }
 }
 
 impl fmt::Display for Type {
@@ -106,3 +147,214 @@ impl FromStr for Type {
         }
     }
 }
+
+// Expiration time of a permission. Parsed from a RFC 3339 timestamp or
+// a duration from now, like 30m, 12h, 7d or 2w
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Expiration(pub DateTime<chrono::Utc>);
+
+impl Expiration {
+    // Drive only accepts expiration times in the future and at most a year from now
+    pub fn is_allowed(&self) -> bool {
+        let now = chrono::Utc::now();
+        self.0 > now && self.0 <= now + chrono::Duration::days(365)
+    }
+}
+
+impl fmt::Display for Expiration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+impl FromStr for Expiration {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Expiration(time.with_timezone(&chrono::Utc)));
+        }
+
+        let err = format!(
+            "'{}' is not a valid expiration, use a duration like 12h, 7d or 2w or a RFC 3339 timestamp like 2023-01-31T12:00:00Z",
+            s
+        );
+
+        let (unit_index, _) = s.char_indices().last().ok_or(err.clone())?;
+        let (amount, unit) = s.split_at(unit_index);
+        let amount: i64 = amount.parse().map_err(|_| err.clone())?;
+
+        if amount <= 0 {
+            return Err(err);
+        }
+
+        let unit_seconds: i64 = match unit {
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => return Err(err),
+        };
+
+        // Amounts too large for a timestamp are rejected instead of overflowing
+        let duration = amount
+            .checked_mul(unit_seconds)
+            .filter(|seconds| *seconds < i64::MAX / 1000)
+            .map(chrono::Duration::seconds)
+            .ok_or(err.clone())?;
+
+        chrono::Utc::now()
+            .checked_add_signed(duration)
+            .map(Expiration)
+            .ok_or(err)
+    }
+}
+
+// Checks the role and expiration against the combinations that drive allows for the type
+pub fn validate_grant(role: &Role, type_: &Type, expires: Option<Expiration>) -> Result<(), Error> {
+    if !type_.supports_role(role) {
+        return Err(Error::UnsupportedRole(role.clone(), type_.clone()));
+    }
+
+    if let Some(expires) = expires {
+        if !type_.supports_expiration() || !role.supports_expiration() {
+            return Err(Error::ExpirationNotSupported(role.clone(), type_.clone()));
+        }
+
+        if !expires.is_allowed() {
+            return Err(Error::InvalidExpiration(expires));
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    UnsupportedRole(Role, Type),
+    ExpirationNotSupported(Role, Type),
+    InvalidExpiration(Expiration),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnsupportedRole(role, type_) => {
+                write!(f, "Role '{}' can't be granted to type '{}'", role, type_)
+            }
+            Error::ExpirationNotSupported(role, type_) => {
+                write!(
+                    f,
+                    "Expiration is only supported for reader, commenter and writer permissions of users and groups, not for role '{}' with type '{}'",
+                    role, type_
+                )
+            }
+            Error::InvalidExpiration(expires) => {
+                write!(
+                    f,
+                    "Expiration {} must be in the future and at most a year from now",
+                    expires
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes_from_now(expiration: Expiration) -> i64 {
+        (expiration.0 - chrono::Utc::now()).num_minutes()
+    }
+
+    #[test]
+    fn expiration_accepts_rfc3339() {
+        let expiration: Expiration = "2023-01-31T12:00:00+01:00".parse().unwrap();
+        assert_eq!(expiration.to_string(), "2023-01-31T11:00:00Z");
+    }
+
+    #[test]
+    fn expiration_accepts_durations() {
+        let minutes: Expiration = "30m".parse().unwrap();
+        let hours: Expiration = "12h".parse().unwrap();
+        let days: Expiration = "7d".parse().unwrap();
+        let weeks: Expiration = "2w".parse().unwrap();
+
+        assert!((29..=30).contains(&minutes_from_now(minutes)));
+        assert!((12 * 60 - 1..=12 * 60).contains(&minutes_from_now(hours)));
+        assert!((7 * 24 * 60 - 1..=7 * 24 * 60).contains(&minutes_from_now(days)));
+        assert!((14 * 24 * 60 - 1..=14 * 24 * 60).contains(&minutes_from_now(weeks)));
+    }
+
+    #[test]
+    fn expiration_rejects_invalid_input() {
+        for input in [
+            "",
+            "d",
+            "5x",
+            "-5d",
+            "0d",
+            "5",
+            "five d",
+            "99999999999999999w",
+        ] {
+            assert!(
+                input.parse::<Expiration>().is_err(),
+                "'{}' should be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn validate_grant_accepts_expiration_within_a_year() {
+        let expires = Expiration(chrono::Utc::now() + chrono::Duration::days(364));
+        assert!(validate_grant(&Role::Reader, &Type::User, Some(expires)).is_ok());
+    }
+
+    #[test]
+    fn validate_grant_rejects_expiration_more_than_a_year_out() {
+        let expires = Expiration(chrono::Utc::now() + chrono::Duration::days(366));
+
+        assert!(matches!(
+            validate_grant(&Role::Reader, &Type::User, Some(expires)),
+            Err(Error::InvalidExpiration(_))
+        ));
+    }
+
+    #[test]
+    fn validate_grant_rejects_expiration_in_the_past() {
+        let expires = Expiration(chrono::Utc::now() - chrono::Duration::minutes(1));
+
+        assert!(matches!(
+            validate_grant(&Role::Writer, &Type::Group, Some(expires)),
+            Err(Error::InvalidExpiration(_))
+        ));
+    }
+
+    #[test]
+    fn validate_grant_rejects_expiration_of_unsupported_permissions() {
+        let expires = Expiration(chrono::Utc::now() + chrono::Duration::days(1));
+
+        assert!(matches!(
+            validate_grant(&Role::Reader, &Type::Anyone, Some(expires)),
+            Err(Error::ExpirationNotSupported(_, _))
+        ));
+
+        assert!(matches!(
+            validate_grant(&Role::Organizer, &Type::User, Some(expires)),
+            Err(Error::ExpirationNotSupported(_, _))
+        ));
+    }
+
+    #[test]
+    fn validate_grant_rejects_unsupported_roles() {
+        assert!(matches!(
+            validate_grant(&Role::Owner, &Type::Group, None),
+            Err(Error::UnsupportedRole(_, _))
+        ));
+    }
+}
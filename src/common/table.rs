@@ -8,6 +8,12 @@ pub struct Table<H: Display, V: Display, const COLUMNS: usize> {
     pub values: Vec<[V; COLUMNS]>,
 }
 
+// Table where the columns are only known at runtime
+pub struct DynamicTable<H: Display, V: Display> {
+    pub header: Vec<H>,
+    pub values: Vec<Vec<V>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct DisplayConfig {
     pub skip_header: bool,
@@ -41,9 +47,35 @@ pub fn write<W: Write, H: Display, V: Display, const COLUMNS: usize>(
     tw.flush()
 }
 
+pub fn write_dynamic<W: Write, H: Display, V: Display>(
+    writer: W,
+    table: DynamicTable<H, V>,
+    config: &DisplayConfig,
+) -> Result<(), io::Error> {
+    let mut tw = TabWriter::new(writer).padding(3);
+
+    if !config.skip_header {
+        writeln!(&mut tw, "{}", to_dynamic_row(config, table.header))?;
+    }
+
+    for value in table.values {
+        writeln!(&mut tw, "{}", to_dynamic_row(config, value))?;
+    }
+
+    tw.flush()
+}
+
 fn to_row<T: Display, const COLUMNS: usize>(
     config: &DisplayConfig,
     columns: [T; COLUMNS],
 ) -> String {
     columns.map(|c| c.to_string()).join(&config.separator)
 }
+
+fn to_dynamic_row<T: Display>(config: &DisplayConfig, columns: Vec<T>) -> String {
+    columns
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<String>>()
+        .join(&config.separator)
+}
@@ -0,0 +1,316 @@
+use crate::common::file_tree;
+use crate::common::file_tree::FileTree;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::PathBuf;
+
+// The journal is a newline delimited json file. It starts with a header, followed by the
+// planned folders and files. Completion records are appended as items are uploaded, which
+// keeps writes cheap and leaves a valid journal behind if the process is interrupted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Record {
+    #[serde(rename_all = "camelCase")]
+    Header {
+        root_path: PathBuf,
+        parents: Option<Vec<String>>,
+    },
+
+    Entry(JournalEntry),
+
+    Completed {
+        path: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub path: PathBuf,
+    pub kind: EntryKind,
+    pub drive_id: String,
+    pub size: u64,
+    pub modified_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum EntryKind {
+    Folder,
+    File,
+    Shortcut,
+}
+
+pub struct UploadJournal {
+    path: PathBuf,
+    writer: fs::File,
+    root_path: PathBuf,
+    parents: Option<Vec<String>>,
+    entries: HashMap<PathBuf, JournalEntry>,
+    completed: HashSet<PathBuf>,
+}
+
+impl UploadJournal {
+    pub fn create(
+        path: &PathBuf,
+        root_path: &PathBuf,
+        parents: &Option<Vec<String>>,
+    ) -> Result<UploadJournal, Error> {
+        let canonical_root_path = root_path
+            .canonicalize()
+            .map_err(|err| Error::CanonicalizePath(root_path.clone(), err))?;
+
+        // An existing journal is never overwritten, it may belong to an interrupted upload
+        let writer = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::AlreadyExists => Error::AlreadyExists(path.clone()),
+                _ => Error::Create(path.clone(), err),
+            })?;
+
+        let mut journal = UploadJournal {
+            path: path.clone(),
+            writer,
+            root_path: canonical_root_path.clone(),
+            parents: parents.clone(),
+            entries: HashMap::new(),
+            completed: HashSet::new(),
+        };
+
+        journal.append(&Record::Header {
+            root_path: canonical_root_path,
+            parents: parents.clone(),
+        })?;
+
+        Ok(journal)
+    }
+
+    pub fn open(path: &PathBuf) -> Result<UploadJournal, Error> {
+        let file = fs::File::open(path).map_err(|err| Error::Open(path.clone(), err))?;
+        let reader = BufReader::new(file);
+
+        let mut header: Option<(PathBuf, Option<Vec<String>>)> = None;
+        let mut entries = HashMap::new();
+        let mut completed = HashSet::new();
+
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| Error::Read(path.clone(), err))?;
+
+            // The last line may be incomplete if the process was killed while writing it
+            let record: Record = match serde_json::from_str(&line) {
+                Ok(record) => record,
+                Err(_) if line.trim().is_empty() => continue,
+                Err(err) => {
+                    eprintln!(
+                        "Warning: Ignoring invalid journal record on line {}: {}",
+                        index + 1,
+                        err
+                    );
+                    continue;
+                }
+            };
+
+            match record {
+                Record::Header { root_path, parents } => {
+                    header = Some((root_path, parents));
+                }
+
+                Record::Entry(entry) => {
+                    entries.insert(entry.path.clone(), entry);
+                }
+
+                Record::Completed { path } => {
+                    completed.insert(path);
+                }
+            }
+        }
+
+        let (root_path, parents) = header.ok_or(Error::MissingHeader(path.clone()))?;
+
+        let writer = fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|err| Error::Open(path.clone(), err))?;
+
+        Ok(UploadJournal {
+            path: path.clone(),
+            writer,
+            root_path,
+            parents,
+            entries,
+            completed,
+        })
+    }
+
+    pub fn root_path(&self) -> PathBuf {
+        self.root_path.clone()
+    }
+
+    pub fn parents(&self) -> Option<Vec<String>> {
+        self.parents.clone()
+    }
+
+    // Drive ids that were assigned to local paths in a previous run
+    pub fn known_ids(&self) -> HashMap<PathBuf, String> {
+        self.entries
+            .values()
+            .map(|entry| (entry.path.clone(), entry.drive_id.clone()))
+            .collect()
+    }
+
+    pub fn entry(&self, path: &PathBuf) -> Option<&JournalEntry> {
+        self.entries.get(path)
+    }
+
+    pub fn is_completed(&self, path: &PathBuf) -> bool {
+        self.completed.contains(path)
+    }
+
+    // Records the planned folders, files and shortcuts that are not already in the journal
+    pub fn add_tree(&mut self, tree: &FileTree) -> Result<(), Error> {
+        for folder in tree.folders() {
+            self.add_entry(JournalEntry {
+                path: folder.path.clone(),
+                kind: EntryKind::Folder,
+                drive_id: folder.drive_id.clone(),
+                size: 0,
+                modified_time: None,
+            })?;
+
+            for file in folder.files() {
+                self.add_entry(file_entry(&file))?;
+            }
+        }
+
+        for shortcut in tree.shortcuts() {
+            self.add_entry(JournalEntry {
+                path: shortcut.path.clone(),
+                kind: EntryKind::Shortcut,
+                drive_id: shortcut.drive_id.clone(),
+                size: 0,
+                modified_time: None,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    // Records the current size and modification time of a file that was updated on drive
+    pub fn update_file(&mut self, file: &file_tree::File) -> Result<(), Error> {
+        let entry = file_entry(file);
+        self.append(&Record::Entry(entry.clone()))?;
+        self.entries.insert(entry.path.clone(), entry);
+        Ok(())
+    }
+
+    // Records an item that was uploaded or moved after the initial upload, replacing
+    // any previous entry for the same path
+    pub fn record(&mut self, entry: JournalEntry) -> Result<(), Error> {
+        self.append(&Record::Entry(entry.clone()))?;
+        self.entries.insert(entry.path.clone(), entry.clone());
+        self.mark_completed(&entry.path)
+    }
+
+    pub fn mark_completed(&mut self, path: &PathBuf) -> Result<(), Error> {
+        if self.completed.insert(path.clone()) {
+            self.append(&Record::Completed { path: path.clone() })?;
+        }
+
+        Ok(())
+    }
+
+    fn add_entry(&mut self, entry: JournalEntry) -> Result<(), Error> {
+        if !self.entries.contains_key(&entry.path) {
+            self.append(&Record::Entry(entry.clone()))?;
+            self.entries.insert(entry.path.clone(), entry);
+        }
+
+        Ok(())
+    }
+
+    fn append(&mut self, record: &Record) -> Result<(), Error> {
+        let line = serde_json::to_string(record).map_err(Error::Serialize)?;
+        writeln!(self.writer, "{}", line).map_err(|err| Error::Write(self.path.clone(), err))
+    }
+}
+
+fn file_entry(file: &file_tree::File) -> JournalEntry {
+    JournalEntry {
+        path: file.path.clone(),
+        kind: EntryKind::File,
+        drive_id: file.drive_id.clone(),
+        size: file.size,
+        modified_time: format_modified_time(file),
+    }
+}
+
+// Checks if the local file has changed since it was recorded in the journal
+pub fn has_changed(entry: &JournalEntry, file: &file_tree::File) -> bool {
+    entry.size != file.size || entry.modified_time != format_modified_time(file)
+}
+
+fn format_modified_time(file: &file_tree::File) -> Option<String> {
+    file.modified_time.map(|time| time.to_rfc3339())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    CanonicalizePath(PathBuf, io::Error),
+    Create(PathBuf, io::Error),
+    AlreadyExists(PathBuf),
+    Open(PathBuf, io::Error),
+    Read(PathBuf, io::Error),
+    Write(PathBuf, io::Error),
+    Serialize(serde_json::Error),
+    MissingHeader(PathBuf),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::CanonicalizePath(path, err) => write!(
+                f,
+                "Failed to get canonical path of {}: {}",
+                path.display(),
+                err
+            ),
+            Error::Create(path, err) => {
+                write!(f, "Failed to create journal '{}': {}", path.display(), err)
+            }
+            Error::AlreadyExists(path) => write!(
+                f,
+                "Journal '{}' already exists, use --resume to continue the upload it belongs to",
+                path.display()
+            ),
+            Error::Open(path, err) => {
+                write!(f, "Failed to open journal '{}': {}", path.display(), err)
+            }
+            Error::Read(path, err) => {
+                write!(f, "Failed to read journal '{}': {}", path.display(), err)
+            }
+            Error::Write(path, err) => {
+                write!(f, "Failed to write journal '{}': {}", path.display(), err)
+            }
+            Error::Serialize(err) => write!(f, "Failed to serialize journal record: {}", err),
+            Error::MissingHeader(path) => write!(
+                f,
+                "Journal '{}' is not a valid upload journal, it is missing the header",
+                path.display()
+            ),
+        }
+    }
+}
@@ -1,3 +1,15 @@
+pub mod create;
+pub mod delete;
+pub mod hide;
+pub mod info;
 pub mod list;
+pub mod rename;
+pub mod unhide;
 
+pub use create::create;
+pub use delete::delete;
+pub use hide::hide;
+pub use info::info;
 pub use list::list;
+pub use rename::rename;
+pub use unhide::unhide;
@@ -0,0 +1,88 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::hub_helper;
+use crate::drives;
+use crate::hub::Hub;
+use std::error;
+use std::fmt;
+
+pub struct Config {
+    pub name: String,
+    pub request_id: Option<String>,
+    pub print_only_id: bool,
+}
+
+pub async fn create(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    // The request id makes the request idempotent, retrying with the same id will not create a second drive
+    let request_id = config
+        .request_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    if !config.print_only_id {
+        println!(
+            "Creating drive '{}' with request id: {}",
+            config.name, request_id
+        );
+    }
+
+    let drive = create_drive(&hub, delegate_config, &config.name, &request_id)
+        .await
+        .map_err(Error::CreateDrive)?;
+
+    if config.print_only_id {
+        print!("{}", drive.id.unwrap_or_default())
+    } else {
+        println!("Drive successfully created");
+        let fields = drives::info::prepare_fields(&drive);
+        drives::info::print_fields(&fields);
+    }
+
+    Ok(())
+}
+
+pub async fn create_drive(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    name: &str,
+    request_id: &str,
+) -> Result<google_drive3::api::Drive, google_drive3::Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let drive = google_drive3::api::Drive {
+        name: Some(name.to_string()),
+        ..google_drive3::api::Drive::default()
+    };
+
+    let (_, drive) = hub
+        .drives()
+        .create(drive, request_id)
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .doit()
+        .await?;
+
+    Ok(drive)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    CreateDrive(google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::CreateDrive(err) => {
+                write!(f, "Failed to create drive: {}", err)
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::hub_helper;
+use crate::drives;
+use crate::files;
+use crate::files::list::ListFilesConfig;
+use crate::files::list::ListQuery;
+use crate::files::list::ListSortOrder;
+use crate::hub::Hub;
+use std::error;
+use std::fmt;
+
+pub struct Config {
+    pub drive_id: String,
+    pub delete_items: bool,
+    pub use_domain_admin_access: bool,
+}
+
+pub async fn delete(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let drive = drives::info::get_drive(
+        &hub,
+        delegate_config.clone(),
+        &config.drive_id,
+        config.use_domain_admin_access,
+    )
+    .await
+    .map_err(Error::GetDrive)?;
+
+    // Domain admins can let the api delete the items together with the drive,
+    // organizers have to empty the drive themselves before it can be deleted
+    if config.delete_items && !config.use_domain_admin_access {
+        delete_drive_items(&hub, delegate_config.clone(), &config.drive_id).await?;
+    }
+
+    delete_drive(&hub, delegate_config, &config)
+        .await
+        .map_err(|err| Error::DeleteDrive(err, config.delete_items))?;
+
+    println!("Deleted drive '{}'", drive.name.unwrap_or_default());
+
+    Ok(())
+}
+
+pub async fn delete_drive(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    config: &Config,
+) -> Result<(), google_drive3::Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let mut req = hub
+        .drives()
+        .delete(&config.drive_id)
+        .use_domain_admin_access(config.use_domain_admin_access);
+
+    // Only allowed together with domain admin access
+    if config.use_domain_admin_access {
+        req = req.allow_item_deletion(config.delete_items);
+    }
+
+    req.add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .doit()
+        .await?;
+
+    Ok(())
+}
+
+// Permanently deletes all top level items of the drive, which also deletes their descendants.
+// Trashed items are included, the drive can't be deleted while they are still in the trash
+async fn delete_drive_items(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    drive_id: &str,
+) -> Result<(), Error> {
+    let items = files::list::list_files(
+        hub,
+        &ListFilesConfig {
+            query: ListQuery::Custom(format!("'{}' in parents", drive_id)),
+            order_by: ListSortOrder::default(),
+            max_files: usize::MAX,
+            fields: None,
+        },
+    )
+    .await
+    .map_err(Error::ListItems)?;
+
+    for item in items {
+        let mut delegate = UploadDelegate::new(delegate_config.clone());
+        let name = item.name.unwrap_or_default();
+        let id = item.id.unwrap_or_default();
+
+        println!("Deleting '{}'", name);
+
+        hub.files()
+            .delete(&id)
+            .supports_all_drives(true)
+            .add_scope(google_drive3::api::Scope::Full)
+            .delegate(&mut delegate)
+            .doit()
+            .await
+            .map_err(|err| Error::DeleteItem(name, err))?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    GetDrive(google_drive3::Error),
+    ListItems(files::list::Error),
+    DeleteItem(String, google_drive3::Error),
+    // The flag tells if the items of the drive were deleted first
+    DeleteDrive(google_drive3::Error, bool),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::GetDrive(err) => {
+                write!(f, "Failed to get drive: {}", err)
+            }
+            Error::ListItems(err) => {
+                write!(f, "Failed to list items on drive: {}", err)
+            }
+            Error::DeleteItem(name, err) => {
+                write!(f, "Failed to delete '{}': {}", name, err)
+            }
+            Error::DeleteDrive(err, true) => {
+                write!(f, "Failed to delete drive: {}", err)
+            }
+            Error::DeleteDrive(err, false) => {
+                write!(
+                    f,
+                    "Failed to delete drive: {}. Use --recursive to delete drives that are not empty",
+                    err
+                )
+            }
+        }
+    }
+}
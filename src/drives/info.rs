@@ -0,0 +1,155 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::hub_helper;
+use crate::files::info::format_bool;
+use crate::files::info::format_date_time;
+use crate::files::info::format_list;
+use crate::files::info::Field;
+use crate::hub::Hub;
+use std::error;
+use std::fmt;
+
+pub use crate::files::info::print_fields;
+
+pub struct Config {
+    pub drive_id: String,
+    pub use_domain_admin_access: bool,
+}
+
+pub async fn info(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let drive = get_drive(
+        &hub,
+        delegate_config,
+        &config.drive_id,
+        config.use_domain_admin_access,
+    )
+    .await
+    .map_err(Error::GetDrive)?;
+
+    let fields = prepare_fields(&drive);
+    print_fields(&fields);
+
+    Ok(())
+}
+
+pub async fn get_drive(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    drive_id: &str,
+    use_domain_admin_access: bool,
+) -> Result<google_drive3::api::Drive, google_drive3::Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let (_, drive) = hub
+        .drives()
+        .get(drive_id)
+        .use_domain_admin_access(use_domain_admin_access)
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .doit()
+        .await?;
+
+    Ok(drive)
+}
+
+pub fn prepare_fields(drive: &google_drive3::api::Drive) -> Vec<Field> {
+    vec![
+        Field {
+            name: String::from("Id"),
+            value: drive.id.clone(),
+        },
+        Field {
+            name: String::from("Name"),
+            value: drive.name.clone(),
+        },
+        Field {
+            name: String::from("Created"),
+            value: drive.created_time.map(format_date_time),
+        },
+        Field {
+            name: String::from("Hidden"),
+            value: drive.hidden.map(format_bool),
+        },
+        Field {
+            name: String::from("Restrictions"),
+            value: drive.restrictions.as_ref().map(format_restrictions),
+        },
+        Field {
+            name: String::from("Capabilities"),
+            value: drive.capabilities.as_ref().map(format_capabilities),
+        },
+    ]
+}
+
+// Lists the restrictions that are enabled on the drive
+pub fn format_restrictions(restrictions: &google_drive3::api::DriveRestrictions) -> String {
+    let flags = [
+        ("adminManaged", restrictions.admin_managed_restrictions),
+        (
+            "copyRequiresWriterPermission",
+            restrictions.copy_requires_writer_permission,
+        ),
+        ("domainUsersOnly", restrictions.domain_users_only),
+        ("driveMembersOnly", restrictions.drive_members_only),
+    ];
+
+    format_enabled_flags(&flags)
+}
+
+// Lists the capabilities the current user has on the drive
+pub fn format_capabilities(capabilities: &google_drive3::api::DriveCapabilities) -> String {
+    let flags = [
+        ("canAddChildren", capabilities.can_add_children),
+        ("canComment", capabilities.can_comment),
+        ("canCopy", capabilities.can_copy),
+        ("canDeleteChildren", capabilities.can_delete_children),
+        ("canDeleteDrive", capabilities.can_delete_drive),
+        ("canDownload", capabilities.can_download),
+        ("canEdit", capabilities.can_edit),
+        ("canListChildren", capabilities.can_list_children),
+        ("canManageMembers", capabilities.can_manage_members),
+        ("canReadRevisions", capabilities.can_read_revisions),
+        ("canRename", capabilities.can_rename),
+        ("canRenameDrive", capabilities.can_rename_drive),
+        ("canShare", capabilities.can_share),
+        ("canTrashChildren", capabilities.can_trash_children),
+    ];
+
+    format_enabled_flags(&flags)
+}
+
+fn format_enabled_flags(flags: &[(&str, Option<bool>)]) -> String {
+    let enabled: Vec<String> = flags
+        .iter()
+        .filter(|(_, value)| value.unwrap_or(false))
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    if enabled.is_empty() {
+        String::from("None")
+    } else {
+        format_list(&enabled)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    GetDrive(google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::GetDrive(err) => {
+                write!(f, "Failed to get drive: {}", err)
+            }
+        }
+    }
+}
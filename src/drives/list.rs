@@ -3,12 +3,18 @@ use crate::common::delegate::UploadDelegateConfig;
 use crate::common::hub_helper;
 use crate::common::table;
 use crate::common::table::Table;
+use crate::drives;
+use crate::files;
 use crate::hub::Hub;
 use std::error;
 use std::fmt;
 use std::io;
 
+const MAX_PAGE_SIZE: i32 = 100;
+
 pub struct Config {
+    pub query: Option<String>,
+    pub use_domain_admin_access: bool,
     pub skip_header: bool,
     pub field_separator: String,
 }
@@ -17,9 +23,16 @@ pub async fn list(config: Config) -> Result<(), Error> {
     let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
 
-    let drives = list_drives(&hub, delegate_config)
-        .await
-        .map_err(Error::ListDrives)?;
+    let drives = list_drives(
+        &hub,
+        delegate_config,
+        &ListDrivesConfig {
+            query: config.query.clone(),
+            use_domain_admin_access: config.use_domain_admin_access,
+        },
+    )
+    .await
+    .map_err(Error::ListDrives)?;
 
     print_drives_table(&config, drives);
 
@@ -27,18 +40,30 @@ pub async fn list(config: Config) -> Result<(), Error> {
 }
 
 fn print_drives_table(config: &Config, drives: Vec<google_drive3::api::Drive>) {
-    let mut values: Vec<[String; 2]> = vec![];
+    let mut values: Vec<[String; 5]> = vec![];
 
     for drive in drives {
         values.push([
-            // fmt
             drive.id.unwrap_or_default(),
             drive.name.unwrap_or_default(),
+            drive
+                .created_time
+                .map(files::info::format_date_time)
+                .unwrap_or_default(),
+            drive
+                .hidden
+                .map(files::info::format_bool)
+                .unwrap_or_default(),
+            drive
+                .restrictions
+                .as_ref()
+                .map(drives::info::format_restrictions)
+                .unwrap_or_default(),
         ])
     }
 
     let table = Table {
-        header: ["Id", "Name"],
+        header: ["Id", "Name", "Created", "Hidden", "Restrictions"],
         values,
     };
 
@@ -52,21 +77,60 @@ fn print_drives_table(config: &Config, drives: Vec<google_drive3::api::Drive>) {
     );
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct ListDrivesConfig {
+    // See https://developers.google.com/drive/api/guides/search-shareddrives
+    pub query: Option<String>,
+    pub use_domain_admin_access: bool,
+}
+
 pub async fn list_drives(
     hub: &Hub,
     delegate_config: UploadDelegateConfig,
+    config: &ListDrivesConfig,
 ) -> Result<Vec<google_drive3::api::Drive>, google_drive3::Error> {
-    let mut delegate = UploadDelegate::new(delegate_config);
+    let mut collected_drives: Vec<google_drive3::api::Drive> = vec![];
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut delegate = UploadDelegate::new(delegate_config.clone());
+
+        let mut req = hub
+            .drives()
+            .list()
+            .page_size(MAX_PAGE_SIZE)
+            .use_domain_admin_access(config.use_domain_admin_access);
+
+        if let Some(token) = &next_page_token {
+            req = req.page_token(token);
+        }
 
-    let (_, drives_list) = hub
-        .drives()
-        .list()
-        .add_scope(google_drive3::api::Scope::Full)
-        .delegate(&mut delegate)
-        .doit()
-        .await?;
+        if let Some(query) = &config.query {
+            req = req.q(query);
+        }
+
+        let (_, drives_list) = req
+            .add_scope(google_drive3::api::Scope::Full)
+            .delegate(&mut delegate)
+            .param(
+                "fields",
+                "drives(id,name,createdTime,hidden,restrictions),nextPageToken",
+            )
+            .doit()
+            .await?;
+
+        if let Some(mut drives) = drives_list.drives {
+            collected_drives.append(&mut drives);
+        }
+
+        next_page_token = drives_list.next_page_token;
+
+        if next_page_token.is_none() {
+            break;
+        }
+    }
 
-    Ok(drives_list.drives.unwrap_or_default())
+    Ok(collected_drives)
 }
 
 #[derive(Debug)]
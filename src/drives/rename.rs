@@ -0,0 +1,80 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::hub_helper;
+use crate::drives;
+use crate::hub::Hub;
+use std::error;
+use std::fmt;
+
+pub struct Config {
+    pub drive_id: String,
+    pub name: String,
+}
+
+pub async fn rename(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let old_drive = drives::info::get_drive(&hub, delegate_config.clone(), &config.drive_id, false)
+        .await
+        .map_err(Error::GetDrive)?;
+
+    println!(
+        "Renaming {} to {}",
+        old_drive.name.unwrap_or_default(),
+        config.name
+    );
+
+    let drive = google_drive3::api::Drive {
+        name: Some(config.name.clone()),
+        ..google_drive3::api::Drive::default()
+    };
+
+    update_drive(&hub, delegate_config, &config.drive_id, drive)
+        .await
+        .map_err(Error::Rename)?;
+
+    Ok(())
+}
+
+pub async fn update_drive(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    drive_id: &str,
+    drive: google_drive3::api::Drive,
+) -> Result<google_drive3::api::Drive, google_drive3::Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let (_, drive) = hub
+        .drives()
+        .update(drive, drive_id)
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .doit()
+        .await?;
+
+    Ok(drive)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    GetDrive(google_drive3::Error),
+    Rename(google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::GetDrive(err) => {
+                write!(f, "Failed to get drive: {}", err)
+            }
+            Error::Rename(err) => {
+                write!(f, "Failed to rename drive: {}", err)
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::hub_helper;
+use crate::hub::Hub;
+use std::error;
+use std::fmt;
+
+pub struct Config {
+    pub drive_id: String,
+}
+
+pub async fn unhide(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let drive = unhide_drive(&hub, delegate_config, &config.drive_id)
+        .await
+        .map_err(Error::Unhide)?;
+
+    println!("Unhid drive '{}'", drive.name.unwrap_or_default());
+
+    Ok(())
+}
+
+pub async fn unhide_drive(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    drive_id: &str,
+) -> Result<google_drive3::api::Drive, google_drive3::Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let (_, drive) = hub
+        .drives()
+        .unhide(drive_id)
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .doit()
+        .await?;
+
+    Ok(drive)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    Unhide(google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::Unhide(err) => {
+                write!(f, "Failed to unhide drive: {}", err)
+            }
+        }
+    }
+}
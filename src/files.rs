@@ -1,27 +1,39 @@
 pub mod copy;
 pub mod delete;
 pub mod download;
+pub mod du;
 pub mod export;
 pub mod generate_ids;
 pub mod import;
 pub mod info;
 pub mod list;
+pub mod list_recursive;
 pub mod mkdir;
 pub mod mv;
 pub mod rename;
+pub mod shortcut;
+pub mod tree;
 pub mod update;
 pub mod upload;
+pub mod watch_download;
+pub mod watch_upload;
 
 pub use copy::copy;
 pub use delete::delete;
 pub use download::download;
+pub use du::du;
 pub use export::export;
 pub use generate_ids::generate_ids;
 pub use import::import;
 pub use info::info;
 pub use list::list;
+pub use list_recursive::list_recursive;
 pub use mkdir::mkdir;
 pub use mv::mv;
 pub use rename::rename;
+pub use shortcut::shortcut;
+pub use tree::tree;
 pub use update::update;
 pub use upload::upload;
+pub use watch_download::watch_download;
+pub use watch_upload::watch_upload;
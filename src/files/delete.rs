@@ -2,10 +2,15 @@ use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
+use crate::common::batch;
+use crate::common::drive_batch;
+use crate::common::drive_batch::Call;
 use crate::common::drive_file;
 use crate::common::hub_helper;
 use crate::files;
+use crate::hub::Hub;
 
+#[derive(Clone, Debug)]
 pub struct Config {
     pub file_id: String,
     pub delete_directories: bool,
@@ -13,12 +18,70 @@ pub struct Config {
 
 pub async fn delete(config: Config) -> Result<(), Error> {
     let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    delete_file(&hub, &config).await
+}
+
+// Deletes each of the files, the file id of the config is ignored.
+// The calls are sent with the batch api to keep the number of requests low
+pub async fn delete_many(
+    config: Config,
+    file_ids: Vec<String>,
+    concurrency: usize,
+) -> Result<(), batch::Error> {
+    let hub = hub_helper::get_hub().await.map_err(batch::Error::Hub)?;
+    let mut summary = batch::Summary::default();
+
+    let get_calls: Vec<Call> = file_ids
+        .iter()
+        .map(|file_id| Call::get_file(file_id, "id,name,mimeType"))
+        .collect();
+
+    let get_results = drive_batch::execute(&hub, &get_calls, concurrency)
+        .await
+        .map_err(batch::Error::DriveBatch)?;
+
+    let mut files_to_delete = vec![];
+
+    for (file_id, result) in file_ids.into_iter().zip(get_results) {
+        match drive_batch::parse::<google_drive3::api::File>(result) {
+            Ok(file) => match err_if_directory(&file, &config) {
+                Ok(()) => files_to_delete.push((file_id, file.name.unwrap_or_default())),
+                Err(err) => summary.add_failure(&file_id, &err),
+            },
+
+            Err(err) => summary.add_failure(&file_id, &err),
+        }
+    }
+
+    let delete_calls: Vec<Call> = files_to_delete
+        .iter()
+        .map(|(file_id, _)| Call::delete_file(file_id))
+        .collect();
+
+    let delete_results = drive_batch::execute(&hub, &delete_calls, concurrency)
+        .await
+        .map_err(batch::Error::DriveBatch)?;
+
+    for ((file_id, name), result) in files_to_delete.into_iter().zip(delete_results) {
+        match result {
+            Ok(_) => {
+                println!("Deleted '{}'", name);
+                summary.add_success();
+            }
+
+            Err(err) => summary.add_failure(&file_id, &err),
+        }
+    }
+
+    summary.finish()
+}
 
-    let file = files::info::get_file(&hub, &config.file_id)
+pub async fn delete_file(hub: &Hub, config: &Config) -> Result<(), Error> {
+    let file = files::info::get_file(hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
-    err_if_directory(&file, &config)?;
+    err_if_directory(&file, config)?;
 
     hub.files()
         .delete(&config.file_id)
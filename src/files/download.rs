@@ -1,12 +1,19 @@
 use crate::common::drive_file;
+use crate::common::existing_file::ExistingFileAction;
+use crate::common::existing_file::Resolution;
+use crate::common::failure_report;
+use crate::common::failure_report::FailureReport;
 use crate::common::file_tree_drive;
 use crate::common::file_tree_drive::FileTreeDrive;
 use crate::common::hub_helper;
+use crate::common::local_name;
 use crate::common::md5_writer::Md5Writer;
 use crate::files;
 use crate::hub::Hub;
 use async_recursion::async_recursion;
 use futures::stream::StreamExt;
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
 use google_drive3::hyper;
 use human_bytes::human_bytes;
 use std::error;
@@ -19,6 +26,7 @@ use std::io::BufReader;
 use std::io::Read;
 use std::io::Write;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
 pub struct Config {
     pub file_id: String,
@@ -26,6 +34,9 @@ pub struct Config {
     pub follow_shortcuts: bool,
     pub download_directories: bool,
     pub destination: Destination,
+    pub manifest_path: Option<PathBuf>,
+    pub keep_going: bool,
+    pub failure_report: Option<PathBuf>,
 }
 
 impl Config {
@@ -65,12 +76,6 @@ pub enum Destination {
     Stdout,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub enum ExistingFileAction {
-    Abort,
-    Overwrite,
-}
-
 #[async_recursion]
 pub async fn download(config: Config) -> Result<(), Error> {
     let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
@@ -107,24 +112,53 @@ pub async fn download_regular(
     file: &google_drive3::api::File,
     config: &Config,
 ) -> Result<(), Error> {
-    let body = download_file(&hub, &config.file_id)
-        .await
-        .map_err(Error::DownloadFile)?;
-
     match &config.destination {
         Destination::Stdout => {
-            // fmt
+            let body = download_file(hub, &config.file_id)
+                .await
+                .map_err(Error::DownloadFile)?;
+
             save_body_to_stdout(body).await?;
         }
 
         _ => {
             let file_name = file.name.clone().ok_or(Error::MissingFileName)?;
             let root_path = config.canonical_destination_root()?;
-            let abs_file_path = root_path.join(&file_name);
+            let abs_file_path = root_path.join(local_name::sanitize(&file_name));
+
+            // Resolved before downloading, so skipped files are not fetched at all
+            match config
+                .existing_file_action
+                .resolve(&abs_file_path, file.modified_time)
+            {
+                Resolution::Write(path) => {
+                    let body = download_file(hub, &config.file_id)
+                        .await
+                        .map_err(Error::DownloadFile)?;
+
+                    // The rename policy writes to another path than the one of the file name
+                    let local_path = path
+                        .strip_prefix(&root_path)
+                        .unwrap_or(&path)
+                        .display()
+                        .to_string();
+
+                    println!("Downloading {}", local_path);
+                    save_body_to_file(body, &path, file.md5_checksum.clone(), file.modified_time)
+                        .await?;
+                    println!("Successfully downloaded {}", local_path);
+                }
 
-            println!("Downloading {}", file_name);
-            save_body_to_file(body, &abs_file_path, file.md5_checksum.clone()).await?;
-            println!("Successfully downloaded {}", file_name);
+                Resolution::Skip => {
+                    // fmt
+                    println!("Skipping existing file {}", file_name);
+                }
+
+                Resolution::Abort => {
+                    // fmt
+                    return Err(Error::FileExists(abs_file_path));
+                }
+            }
         }
     }
 
@@ -135,7 +169,7 @@ pub async fn download_directory(
     hub: &Hub,
     file: &google_drive3::api::File,
     config: &Config,
-) -> Result<(), Error> {
+) -> Result<FileTreeDrive, Error> {
     let tree = FileTreeDrive::from_file(&hub, &file)
         .await
         .map_err(Error::CreateFileTree)?;
@@ -149,33 +183,62 @@ pub async fn download_directory(
         human_bytes(tree_info.total_file_size as f64)
     );
 
+    for entry in tree.renamed_entries() {
+        eprintln!(
+            "Warning: '{}' will be saved as '{}'",
+            entry.drive_name, entry.local_path
+        );
+    }
+
     let root_path = config.canonical_destination_root()?;
 
+    if let Some(path) = &config.manifest_path {
+        save_manifest(&tree, path)?;
+    }
+
+    let mut report = FailureReport::new();
+
     for folder in &tree.folders() {
         let folder_path = folder.relative_path();
         let abs_folder_path = root_path.join(&folder_path);
 
         println!("Creating directory {}", folder_path.display());
-        fs::create_dir_all(&abs_folder_path)
-            .map_err(|err| Error::CreateDirectory(abs_folder_path, err))?;
 
-        for file in folder.files() {
-            let file_path = file.relative_path();
-            let abs_file_path = root_path.join(&file_path);
+        if let Err(err) = fs::create_dir_all(&abs_folder_path) {
+            let err = Error::CreateDirectory(abs_folder_path, err);
 
-            if local_file_is_identical(&abs_file_path, &file) {
-                continue;
+            if !config.keep_going {
+                return Err(err);
             }
 
-            let body = download_file(&hub, &file.drive_id)
-                .await
-                .map_err(Error::DownloadFile)?;
+            report.add(&folder_path, &err);
+
+            for file in folder.files() {
+                report.add(&file.relative_path(), &Error::ParentDirectoryFailed);
+            }
+
+            continue;
+        }
 
-            println!("Downloading file '{}'", file_path.display());
-            save_body_to_file(body, &abs_file_path, file.md5.clone()).await?;
+        for file in folder.files() {
+            match download_tree_file(hub, config, &root_path, &file).await {
+                Ok(()) => {}
+                Err(err) if config.keep_going => report.add(&file.relative_path(), &err),
+                Err(err) => return Err(err),
+            }
         }
     }
 
+    if !report.is_empty() {
+        report.print_summary();
+
+        if let Some(path) = &config.failure_report {
+            report.save(path).map_err(Error::SaveFailureReport)?;
+        }
+
+        return Err(Error::PartialFailure(report.len()));
+    }
+
     println!(
         "Downloaded {} files in {} directories with a total size of {}",
         tree_info.file_count,
@@ -183,7 +246,45 @@ pub async fn download_directory(
         human_bytes(tree_info.total_file_size as f64)
     );
 
-    Ok(())
+    Ok(tree)
+}
+
+async fn download_tree_file(
+    hub: &Hub,
+    config: &Config,
+    root_path: &PathBuf,
+    file: &file_tree_drive::File,
+) -> Result<(), Error> {
+    let file_path = file.relative_path();
+    let abs_file_path = root_path.join(&file_path);
+
+    if local_file_is_identical(&abs_file_path, &file.md5) {
+        return Ok(());
+    }
+
+    let abs_file_path = match config
+        .existing_file_action
+        .resolve(&abs_file_path, file.modified_time)
+    {
+        Resolution::Write(path) => path,
+
+        Resolution::Skip => {
+            println!("Skipping existing file '{}'", file_path.display());
+            return Ok(());
+        }
+
+        Resolution::Abort => {
+            // fmt
+            return Err(Error::FileExists(abs_file_path));
+        }
+    };
+
+    let body = download_file(hub, &file.drive_id)
+        .await
+        .map_err(Error::DownloadFile)?;
+
+    println!("Downloading file '{}'", file_path.display());
+    save_body_to_file(body, &abs_file_path, file.md5.clone(), file.modified_time).await
 }
 
 pub async fn download_file(hub: &Hub, file_id: &str) -> Result<hyper::Body, google_drive3::Error> {
@@ -221,6 +322,20 @@ pub enum Error {
     MissingShortcutTarget,
     IsShortcut(String),
     StdoutNotValidDestination,
+    SerializeManifest(serde_json::Error),
+    WriteManifest(PathBuf, io::Error),
+    ParentDirectoryFailed,
+    SaveFailureReport(failure_report::Error),
+    PartialFailure(usize),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::PartialFailure(_) => failure_report::PARTIAL_FAILURE_EXIT_CODE,
+            _ => 1,
+        }
+    }
 }
 
 impl error::Error for Error {}
@@ -234,7 +349,7 @@ impl Display for Error {
             Error::MissingFileName => write!(f, "File does not have a name"),
             Error::FileExists(path) => write!(
                 f,
-                "File '{}' already exists, use --overwrite to overwrite it",
+                "File '{}' already exists, use --overwrite or --on-conflict to handle existing files",
                 path.display()
             ),
             Error::IsDirectory(name) => write!(
@@ -288,6 +403,18 @@ impl Display for Error {
                 f,
                 "Stdout is not a valid destination for this combination of options"
             ),
+            Error::SerializeManifest(err) => write!(f, "Failed to serialize manifest: {}", err),
+            Error::WriteManifest(path, err) => write!(
+                f,
+                "Failed to write manifest '{}': {}",
+                path.display(),
+                err
+            ),
+            Error::ParentDirectoryFailed => {
+                write!(f, "Skipped because the parent directory was not created")
+            }
+            Error::SaveFailureReport(err) => write!(f, "{}", err),
+            Error::PartialFailure(count) => write!(f, "Failed to download {} items", count),
         }
     }
 }
@@ -297,6 +424,7 @@ pub async fn save_body_to_file(
     mut body: hyper::Body,
     file_path: &PathBuf,
     expected_md5: Option<String>,
+    modified_time: Option<DateTime<chrono::Utc>>,
 ) -> Result<(), Error> {
     // Create temporary file
     let tmp_file_path = file_path.with_extension("incomplete");
@@ -315,7 +443,20 @@ pub async fn save_body_to_file(
     err_if_md5_mismatch(expected_md5, writer.md5())?;
 
     // Rename temporary file to final file
-    fs::rename(&tmp_file_path, &file_path).map_err(Error::RenameFile)
+    fs::rename(&tmp_file_path, &file_path).map_err(Error::RenameFile)?;
+
+    // Set local modification time to the modification time on drive
+    if let Some(time) = modified_time {
+        if let Err(err) = set_modified_time(file_path, time.into()) {
+            eprintln!(
+                "Warning: Failed to set modification time of '{}': {}",
+                file_path.display(),
+                err
+            );
+        }
+    }
+
+    Ok(())
 }
 
 // TODO: move to common
@@ -331,9 +472,22 @@ pub async fn save_body_to_stdout(mut body: hyper::Body) -> Result<(), Error> {
     Ok(())
 }
 
+fn save_manifest(tree: &FileTreeDrive, path: &PathBuf) -> Result<(), Error> {
+    let content =
+        serde_json::to_string_pretty(&tree.manifest()).map_err(Error::SerializeManifest)?;
+    fs::write(path, content).map_err(|err| Error::WriteManifest(path.clone(), err))
+}
+
+fn set_modified_time(path: &PathBuf, time: SystemTime) -> Result<(), io::Error> {
+    let file = fs::OpenOptions::new().write(true).open(path)?;
+    file.set_modified(time)
+}
+
 fn err_if_file_exists(file: &google_drive3::api::File, config: &Config) -> Result<(), Error> {
     let file_name = file.name.clone().ok_or(Error::MissingFileName)?;
 
+    let file_name = local_name::sanitize(&file_name);
+
     let file_path = match &config.destination {
         Destination::CurrentDir => Some(PathBuf::from(".").join(file_name)),
         Destination::Path(path) => Some(path.join(file_name)),
@@ -403,7 +557,7 @@ fn err_if_md5_mismatch(expected: Option<String>, actual: String) -> Result<(), E
     }
 }
 
-fn local_file_is_identical(path: &PathBuf, file: &file_tree_drive::File) -> bool {
+pub fn local_file_is_identical(path: &PathBuf, md5: &Option<String>) -> bool {
     if path.exists() {
         let file_md5 = compute_md5_from_path(path).unwrap_or_else(|err| {
             eprintln!(
@@ -415,7 +569,7 @@ fn local_file_is_identical(path: &PathBuf, file: &file_tree_drive::File) -> bool
             String::new()
         });
 
-        file.md5.clone().map(|md5| md5 == file_md5).unwrap_or(false)
+        md5.clone().map(|md5| md5 == file_md5).unwrap_or(false)
     } else {
         false
     }
@@ -0,0 +1,233 @@
+use crate::common::drive_file;
+use crate::common::file_tree_drive;
+use crate::common::file_tree_drive::FileTreeDrive;
+use crate::common::file_tree_drive::Node;
+use crate::common::file_tree_drive::TreeInfo;
+use crate::common::hub_helper;
+use crate::common::table;
+use crate::common::table::Table;
+use crate::files;
+use crate::files::info::DisplayConfig;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+use std::str::FromStr;
+
+pub struct Config {
+    pub folder_id: String,
+    pub group_by: GroupBy,
+    pub size_in_bytes: bool,
+    pub skip_header: bool,
+    pub field_separator: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum GroupBy {
+    #[default]
+    Folder,
+    Mime,
+    Owner,
+}
+
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GroupBy::Folder => write!(f, "folder"),
+            GroupBy::Mime => write!(f, "mime"),
+            GroupBy::Owner => write!(f, "owner"),
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "folder" => Ok(GroupBy::Folder),
+            "mime" => Ok(GroupBy::Mime),
+            "owner" => Ok(GroupBy::Owner),
+            _ => Err(format!(
+                "'{}' is not a valid grouping, valid values are: folder, mime, owner",
+                s
+            )),
+        }
+    }
+}
+
+// Total size and file count of a group of files
+#[derive(Debug, Clone, Default)]
+struct Usage {
+    name: String,
+    file_count: u128,
+    total_file_size: u128,
+}
+
+pub async fn du(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+
+    let file = files::info::get_file(&hub, &config.folder_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    if !drive_file::is_directory(&file) {
+        return Err(Error::NotADirectory(file.name.unwrap_or_default()));
+    }
+
+    let tree = FileTreeDrive::from_file(&hub, &file)
+        .await
+        .map_err(Error::CreateFileTree)?;
+
+    let mut usages = match config.group_by {
+        GroupBy::Folder => usage_by_folder(&tree),
+        GroupBy::Mime => usage_by(&tree, |file| {
+            vec![file.mime_type.clone().unwrap_or_default()]
+        }),
+        GroupBy::Owner => usage_by(&tree, |file| file.owners.clone()),
+    };
+
+    usages.sort_by(|a, b| {
+        b.total_file_size
+            .cmp(&a.total_file_size)
+            .then(a.name.cmp(&b.name))
+    });
+
+    let total = tree.info();
+    print_usage_table(&config, usages, total);
+
+    Ok(())
+}
+
+// Usage of each direct subfolder, files directly in the folder are grouped under '.'
+fn usage_by_folder(tree: &FileTreeDrive) -> Vec<Usage> {
+    let mut usages = vec![];
+    let mut own_files = Usage {
+        name: String::from("."),
+        ..Usage::default()
+    };
+
+    for child in &tree.root.children {
+        match child {
+            Node::FolderNode(folder) => {
+                let info = FileTreeDrive {
+                    root: folder.clone(),
+                }
+                .info();
+
+                usages.push(Usage {
+                    name: folder.name.clone(),
+                    file_count: info.file_count,
+                    total_file_size: info.total_file_size,
+                });
+            }
+
+            Node::FileNode(file) => {
+                own_files.file_count += 1;
+                own_files.total_file_size += file.size as u128;
+            }
+        }
+    }
+
+    if own_files.file_count > 0 {
+        usages.push(own_files);
+    }
+
+    usages
+}
+
+// Usage of all files in the tree grouped by the keys returned for each file
+fn usage_by<F: Fn(&file_tree_drive::File) -> Vec<String>>(
+    tree: &FileTreeDrive,
+    keys: F,
+) -> Vec<Usage> {
+    let mut usages: HashMap<String, Usage> = HashMap::new();
+
+    for folder in tree.folders() {
+        for file in folder.files() {
+            let mut file_keys = keys(&file);
+
+            // Files on shared drives don't have owners
+            if file_keys.is_empty() {
+                file_keys.push(String::from("-"));
+            }
+
+            for key in file_keys {
+                let usage = usages.entry(key.clone()).or_insert(Usage {
+                    name: key,
+                    ..Usage::default()
+                });
+
+                usage.file_count += 1;
+                usage.total_file_size += file.size as u128;
+            }
+        }
+    }
+
+    usages.into_values().collect()
+}
+
+fn print_usage_table(config: &Config, usages: Vec<Usage>, total: TreeInfo) {
+    let display_config = DisplayConfig {
+        size_in_bytes: config.size_in_bytes,
+    };
+
+    let mut values: Vec<[String; 3]> = vec![];
+
+    for usage in usages {
+        values.push([
+            usage.name,
+            usage.file_count.to_string(),
+            files::info::format_bytes(usage.total_file_size as i64, &display_config),
+        ])
+    }
+
+    values.push([
+        String::from("Total"),
+        total.file_count.to_string(),
+        files::info::format_bytes(total.total_file_size as i64, &display_config),
+    ]);
+
+    let name_header = match config.group_by {
+        GroupBy::Folder => "Folder",
+        GroupBy::Mime => "Mime",
+        GroupBy::Owner => "Owner",
+    };
+
+    let table = Table {
+        header: [name_header, "Files", "Size"],
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: config.field_separator.clone(),
+        },
+    );
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    GetFile(google_drive3::Error),
+    NotADirectory(String),
+    CreateFileTree(file_tree_drive::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::GetFile(err) => write!(f, "Failed getting file: {}", err),
+            Error::NotADirectory(name) => write!(f, "'{}' is not a directory", name),
+            Error::CreateFileTree(err) => write!(f, "Failed to create file tree: {}", err),
+        }
+    }
+}
@@ -3,6 +3,8 @@ use mime::Mime;
 use crate::common::drive_file;
 use crate::common::drive_file::DocType;
 use crate::common::drive_file::FileExtension;
+use crate::common::existing_file::ExistingFileAction;
+use crate::common::existing_file::Resolution;
 use crate::common::hub_helper;
 use crate::files;
 use crate::hub::Hub;
@@ -18,12 +20,6 @@ pub struct Config {
     pub existing_file_action: ExistingFileAction,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub enum ExistingFileAction {
-    Abort,
-    Overwrite,
-}
-
 pub async fn export(config: Config) -> Result<(), Error> {
     let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
 
@@ -46,6 +42,23 @@ pub async fn export(config: Config) -> Result<(), Error> {
         .get_export_mime()
         .ok_or(Error::GetFileExtensionMime(extension.clone()))?;
 
+    let file_path = match config
+        .existing_file_action
+        .resolve(&config.file_path, file.modified_time)
+    {
+        Resolution::Write(path) => path,
+
+        Resolution::Skip => {
+            println!("Skipping existing file {}", config.file_path.display());
+            return Ok(());
+        }
+
+        Resolution::Abort => {
+            // fmt
+            return Err(Error::FileExists(config.file_path.clone()));
+        }
+    };
+
     let body = export_file(&hub, &config.file_id, &mime_type)
         .await
         .map_err(Error::ExportFile)?;
@@ -54,14 +67,14 @@ pub async fn export(config: Config) -> Result<(), Error> {
         "Exporting {} '{}' to {}",
         doc_type,
         file.name.unwrap_or_default(),
-        config.file_path.display()
+        file_path.display()
     );
 
-    files::download::save_body_to_file(body, &config.file_path, file.md5_checksum)
+    files::download::save_body_to_file(body, &file_path, file.md5_checksum, file.modified_time)
         .await
         .map_err(Error::SaveFile)?;
 
-    println!("Successfully exported {}", config.file_path.display());
+    println!("Successfully exported {}", file_path.display());
 
     Ok(())
 }
@@ -103,7 +116,7 @@ impl Display for Error {
             Error::FileExists(path) => {
                 write!(
                     f,
-                    "File '{}' already exists, use --overwrite to overwrite it",
+                    "File '{}' already exists, use --overwrite or --on-conflict to handle existing files",
                     path.display()
                 )
             }
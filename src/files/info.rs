@@ -20,13 +20,22 @@ pub async fn info(config: Config) -> Result<(), Error> {
         .await
         .map_err(Error::GetFile)?;
 
-    let fields = prepare_fields(
+    let mut fields = prepare_fields(
         &file,
         &DisplayConfig {
             size_in_bytes: config.size_in_bytes,
         },
     );
 
+    if let Some(target_id) = shortcut_target_id(&file) {
+        let exists = shortcut_target_exists(&hub, &target_id).await?;
+
+        fields.push(Field {
+            name: String::from("ShortcutTargetExists"),
+            value: Some(format_bool(exists)),
+        });
+    }
+
     print_fields(&fields);
 
     Ok(())
@@ -108,9 +117,46 @@ pub fn prepare_fields(file: &google_drive3::api::File, config: &DisplayConfig) -
             name: String::from("ViewUrl"),
             value: file.web_view_link.clone(),
         },
+        Field {
+            name: String::from("ShortcutTarget"),
+            value: shortcut_target_id(file),
+        },
+        Field {
+            name: String::from("ShortcutTargetMime"),
+            value: file
+                .shortcut_details
+                .as_ref()
+                .and_then(|details| details.target_mime_type.clone()),
+        },
     ]
 }
 
+fn shortcut_target_id(file: &google_drive3::api::File) -> Option<String> {
+    file.shortcut_details
+        .as_ref()
+        .and_then(|details| details.target_id.clone())
+}
+
+// Shortcuts are kept when their target is deleted or access to it is lost, trashed
+// targets are also reported as missing. Drive answers with not found for both
+// deleted files and files the user has no access to
+async fn shortcut_target_exists(hub: &Hub, target_id: &str) -> Result<bool, Error> {
+    let result = hub
+        .files()
+        .get(target_id)
+        .param("fields", "id,trashed")
+        .supports_all_drives(true)
+        .add_scope(google_drive3::api::Scope::Full)
+        .doit()
+        .await;
+
+    match result {
+        Ok((_, target)) => Ok(!target.trashed.unwrap_or(false)),
+        Err(err) if hub_helper::error_status(&err) == Some(404) => Ok(false),
+        Err(err) => Err(Error::GetShortcutTarget(err)),
+    }
+}
+
 // TODO: move to common
 pub fn format_bool(b: bool) -> String {
     if b {
@@ -141,6 +187,7 @@ pub fn format_date_time(utc_time: DateTime<chrono::Utc>) -> String {
 pub enum Error {
     Hub(hub_helper::Error),
     GetFile(google_drive3::Error),
+    GetShortcutTarget(google_drive3::Error),
 }
 
 impl error::Error for Error {}
@@ -150,6 +197,9 @@ impl Display for Error {
         match self {
             Error::Hub(err) => write!(f, "{}", err),
             Error::GetFile(err) => write!(f, "Failed getting file: {}", err),
+            Error::GetShortcutTarget(err) => {
+                write!(f, "Failed getting shortcut target: {}", err)
+            }
         }
     }
 }
@@ -1,7 +1,9 @@
-use crate::common::drive_file;
+use crate::common::drive_path::DrivePathResolver;
+use crate::common::file_query::FileQuery;
+use crate::common::file_query::FileTypeFilter;
 use crate::common::hub_helper;
 use crate::common::table;
-use crate::common::table::Table;
+use crate::common::table::DynamicTable;
 use crate::files;
 use crate::files::info::DisplayConfig;
 use crate::hub::Hub;
@@ -15,10 +17,15 @@ use std::str::FromStr;
 
 const MAX_PAGE_SIZE: usize = 1000;
 
+// Fields requested when the caller doesn't ask for specific fields
+const DEFAULT_FILE_FIELDS: &str =
+    "id,name,md5Checksum,mimeType,size,createdTime,modifiedTime,parents,owners(emailAddress)";
+
 pub struct Config {
     pub query: ListQuery,
     pub order_by: ListSortOrder,
     pub max_files: usize,
+    pub columns: Vec<ListColumn>,
     pub skip_header: bool,
     pub truncate_name: bool,
     pub field_separator: String,
@@ -32,35 +39,35 @@ pub async fn list(config: Config) -> Result<(), Error> {
             query: config.query.clone(),
             order_by: config.order_by.clone(),
             max_files: config.max_files,
+            fields: Some(columns_to_fields(&config.columns)),
         },
     )
     .await?;
 
-    let mut values: Vec<[String; 5]> = vec![];
+    let mut path_resolver = DrivePathResolver::new();
+    let mut values: Vec<Vec<String>> = vec![];
 
     for file in files {
-        let file_type = simplified_file_type(&file);
-        let file_name = format_file_name(&config, &file);
-
-        values.push([
-            file.id.unwrap_or_default(),
-            file_name,
-            file_type,
-            file.size
-                .map(|bytes| files::info::format_bytes(bytes, &DisplayConfig::default()))
-                .unwrap_or_default(),
-            file.created_time
-                .map(files::info::format_date_time)
-                .unwrap_or_default(),
-        ])
+        let mut row = vec![];
+
+        for column in &config.columns {
+            let value = match column {
+                ListColumn::Path => path_resolver.path(&hub, &file).await,
+                _ => format_column(&config, column, &file),
+            };
+
+            row.push(value);
+        }
+
+        values.push(row);
     }
 
-    let table = Table {
-        header: ["Id", "Name", "Type", "Size", "Created"],
+    let table = DynamicTable {
+        header: config.columns.iter().map(|c| c.header()).collect(),
         values,
     };
 
-    let _ = table::write(
+    let _ = table::write_dynamic(
         io::stdout(),
         table,
         &table::DisplayConfig {
@@ -72,10 +79,75 @@ pub async fn list(config: Config) -> Result<(), Error> {
     Ok(())
 }
 
+fn format_column(config: &Config, column: &ListColumn, file: &google_drive3::api::File) -> String {
+    match column {
+        ListColumn::Id => file.id.clone().unwrap_or_default(),
+        ListColumn::Name => format_file_name(config, file),
+        ListColumn::Type => simplified_file_type(file),
+        ListColumn::Size => file
+            .size
+            .map(|bytes| files::info::format_bytes(bytes, &DisplayConfig::default()))
+            .unwrap_or_default(),
+        ListColumn::Created => file
+            .created_time
+            .map(files::info::format_date_time)
+            .unwrap_or_default(),
+        ListColumn::Modified => file
+            .modified_time
+            .map(files::info::format_date_time)
+            .unwrap_or_default(),
+        ListColumn::Md5 => file.md5_checksum.clone().unwrap_or_default(),
+        ListColumn::Owners => file
+            .owners
+            .as_ref()
+            .map(|owners| {
+                owners
+                    .iter()
+                    .filter_map(|owner| owner.email_address.clone())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            })
+            .unwrap_or_default(),
+        ListColumn::Parents => file
+            .parents
+            .as_ref()
+            .map(|parents| parents.join(","))
+            .unwrap_or_default(),
+        ListColumn::Mime => file.mime_type.clone().unwrap_or_default(),
+        ListColumn::Shared => file
+            .shared
+            .map(files::info::format_bool)
+            .unwrap_or_default(),
+        ListColumn::Starred => file
+            .starred
+            .map(files::info::format_bool)
+            .unwrap_or_default(),
+        ListColumn::WebViewLink => file.web_view_link.clone().unwrap_or_default(),
+        ListColumn::Path => String::new(),
+    }
+}
+
+// Only the fields needed for the given columns are requested from the api
+fn columns_to_fields(columns: &[ListColumn]) -> String {
+    let mut fields: Vec<&str> = vec![];
+
+    for column in columns {
+        for field in column.fields() {
+            if !fields.contains(field) {
+                fields.push(field);
+            }
+        }
+    }
+
+    fields.join(",")
+}
+
 pub struct ListFilesConfig {
     pub query: ListQuery,
     pub order_by: ListSortOrder,
     pub max_files: usize,
+    // File fields to request, i.e. 'id,name,parents'. Uses a default set of fields if none
+    pub fields: Option<String>,
 }
 
 pub async fn list_files(
@@ -84,6 +156,7 @@ pub async fn list_files(
 ) -> Result<Vec<google_drive3::api::File>, Error> {
     let mut collected_files: Vec<google_drive3::api::File> = vec![];
     let mut next_page_token: Option<String> = None;
+    let file_fields = config.fields.as_deref().unwrap_or(DEFAULT_FILE_FIELDS);
 
     loop {
         let max_files = config.max_files - collected_files.len();
@@ -102,10 +175,7 @@ pub async fn list_files(
             .add_scope(google_drive3::api::Scope::Full)
             .supports_all_drives(true)
             .include_items_from_all_drives(true)
-            .param(
-                "fields",
-                "files(id,name,md5Checksum,mimeType,size,createdTime,parents),nextPageToken",
-            )
+            .param("fields", &format!("files({}),nextPageToken", file_fields))
             .doit()
             .await
             .map_err(Error::ListFiles)?;
@@ -135,6 +205,7 @@ pub enum ListQuery {
     FilesInFolder {
         folder_id: String,
     },
+    Filter(FileQuery),
     Custom(String),
     None,
 }
@@ -166,6 +237,10 @@ impl Display for ListQuery {
                 write!(f, "'{}' in parents and trashed = false", folder_id)
             }
 
+            ListQuery::Filter(query) => {
+                write!(f, "{}", query)
+            }
+
             ListQuery::Custom(query) => {
                 write!(f, "{}", query)
             }
@@ -177,6 +252,123 @@ impl Display for ListQuery {
     }
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ListColumn {
+    Id,
+    Name,
+    Type,
+    Size,
+    Created,
+    Modified,
+    Md5,
+    Owners,
+    Parents,
+    Mime,
+    Shared,
+    Starred,
+    WebViewLink,
+    Path,
+}
+
+impl ListColumn {
+    pub fn default_columns() -> Vec<ListColumn> {
+        vec![
+            ListColumn::Id,
+            ListColumn::Name,
+            ListColumn::Type,
+            ListColumn::Size,
+            ListColumn::Created,
+        ]
+    }
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            ListColumn::Id => "Id",
+            ListColumn::Name => "Name",
+            ListColumn::Type => "Type",
+            ListColumn::Size => "Size",
+            ListColumn::Created => "Created",
+            ListColumn::Modified => "Modified",
+            ListColumn::Md5 => "MD5",
+            ListColumn::Owners => "Owners",
+            ListColumn::Parents => "Parents",
+            ListColumn::Mime => "Mime",
+            ListColumn::Shared => "Shared",
+            ListColumn::Starred => "Starred",
+            ListColumn::WebViewLink => "ViewUrl",
+            ListColumn::Path => "Path",
+        }
+    }
+
+    // Api fields needed to display the column
+    fn fields(&self) -> &'static [&'static str] {
+        match self {
+            ListColumn::Id => &["id"],
+            ListColumn::Name => &["name"],
+            ListColumn::Type => &["mimeType", "md5Checksum"],
+            ListColumn::Size => &["size"],
+            ListColumn::Created => &["createdTime"],
+            ListColumn::Modified => &["modifiedTime"],
+            ListColumn::Md5 => &["md5Checksum"],
+            ListColumn::Owners => &["owners(emailAddress)"],
+            ListColumn::Parents => &["parents"],
+            ListColumn::Mime => &["mimeType"],
+            ListColumn::Shared => &["shared"],
+            ListColumn::Starred => &["starred"],
+            ListColumn::WebViewLink => &["webViewLink"],
+            ListColumn::Path => &["name", "parents"],
+        }
+    }
+}
+
+impl fmt::Display for ListColumn {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ListColumn::Id => write!(f, "id"),
+            ListColumn::Name => write!(f, "name"),
+            ListColumn::Type => write!(f, "type"),
+            ListColumn::Size => write!(f, "size"),
+            ListColumn::Created => write!(f, "created"),
+            ListColumn::Modified => write!(f, "modified"),
+            ListColumn::Md5 => write!(f, "md5"),
+            ListColumn::Owners => write!(f, "owners"),
+            ListColumn::Parents => write!(f, "parents"),
+            ListColumn::Mime => write!(f, "mime"),
+            ListColumn::Shared => write!(f, "shared"),
+            ListColumn::Starred => write!(f, "starred"),
+            ListColumn::WebViewLink => write!(f, "web-view-link"),
+            ListColumn::Path => write!(f, "path"),
+        }
+    }
+}
+
+impl FromStr for ListColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(ListColumn::Id),
+            "name" => Ok(ListColumn::Name),
+            "type" => Ok(ListColumn::Type),
+            "size" => Ok(ListColumn::Size),
+            "created" => Ok(ListColumn::Created),
+            "modified" => Ok(ListColumn::Modified),
+            "md5" => Ok(ListColumn::Md5),
+            "owners" => Ok(ListColumn::Owners),
+            "parents" => Ok(ListColumn::Parents),
+            "mime" => Ok(ListColumn::Mime),
+            "shared" => Ok(ListColumn::Shared),
+            "starred" => Ok(ListColumn::Starred),
+            "web-view-link" => Ok(ListColumn::WebViewLink),
+            "path" => Ok(ListColumn::Path),
+            _ => Err(format!(
+                "'{}' is not a valid column, valid columns are: id, name, type, size, created, modified, md5, owners, parents, mime, shared, starred, web-view-link, path",
+                s
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub enum ListSortOrder {
     #[default]
@@ -227,16 +419,8 @@ impl Display for Error {
     }
 }
 
-fn simplified_file_type(file: &google_drive3::api::File) -> String {
-    if drive_file::is_directory(file) {
-        String::from("folder")
-    } else if drive_file::is_binary(file) {
-        String::from("regular")
-    } else if drive_file::is_shortcut(file) {
-        String::from("shortcut")
-    } else {
-        String::from("document")
-    }
+pub fn simplified_file_type(file: &google_drive3::api::File) -> String {
+    FileTypeFilter::of(file).to_string()
 }
 
 fn format_file_name(config: &Config, file: &google_drive3::api::File) -> String {
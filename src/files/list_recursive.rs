@@ -0,0 +1,129 @@
+use crate::common::drive_file;
+use crate::common::file_query::FileTypeFilter;
+use crate::common::file_tree_drive;
+use crate::common::file_tree_drive::FileTreeDrive;
+use crate::common::file_tree_drive::Node;
+use crate::common::file_tree_drive::TreeOptions;
+use crate::common::hub_helper;
+use crate::common::table;
+use crate::common::table::Table;
+use crate::files;
+use crate::files::info::DisplayConfig;
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+use std::path::PathBuf;
+
+pub struct Config {
+    pub folder_id: String,
+    pub max_depth: Option<usize>,
+    pub skip_header: bool,
+    pub field_separator: String,
+}
+
+pub async fn list_recursive(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+
+    let file = files::info::get_file(&hub, &config.folder_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    if !drive_file::is_directory(&file) {
+        return Err(Error::NotADirectory(file.name.unwrap_or_default()));
+    }
+
+    let tree = FileTreeDrive::from_file_with_options(
+        &hub,
+        &file,
+        &TreeOptions {
+            include_documents: true,
+            max_depth: config.max_depth,
+        },
+    )
+    .await
+    .map_err(Error::CreateFileTree)?;
+
+    let mut values: Vec<[String; 5]> = vec![];
+    collect_rows(&tree.root, &PathBuf::new(), &mut values);
+
+    let table = Table {
+        header: ["Id", "Path", "Type", "Size", "Modified"],
+        values,
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: config.field_separator,
+        },
+    );
+
+    Ok(())
+}
+
+// Adds a row for each descendant of the folder, folders are followed by their content
+fn collect_rows(folder: &file_tree_drive::Folder, path: &PathBuf, values: &mut Vec<[String; 5]>) {
+    for child in folder.sorted_children() {
+        let child_path = path.join(child.name());
+
+        match child {
+            Node::FolderNode(child_folder) => {
+                values.push([
+                    child_folder.drive_id.clone(),
+                    child_path.display().to_string(),
+                    String::from("folder"),
+                    String::new(),
+                    format_modified_time(&child_folder.modified_time),
+                ]);
+
+                collect_rows(&child_folder, &child_path, values);
+            }
+
+            Node::FileNode(file) => {
+                let size = if file.file_type == FileTypeFilter::Regular {
+                    files::info::format_bytes(file.size as i64, &DisplayConfig::default())
+                } else {
+                    String::new()
+                };
+
+                values.push([
+                    file.drive_id.clone(),
+                    child_path.display().to_string(),
+                    file.file_type.to_string(),
+                    size,
+                    format_modified_time(&file.modified_time),
+                ]);
+            }
+        }
+    }
+}
+
+fn format_modified_time(time: &Option<DateTime<chrono::Utc>>) -> String {
+    time.map(files::info::format_date_time).unwrap_or_default()
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    GetFile(google_drive3::Error),
+    NotADirectory(String),
+    CreateFileTree(file_tree_drive::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::GetFile(err) => write!(f, "Failed getting file: {}", err),
+            Error::NotADirectory(name) => write!(f, "'{}' is not a directory", name),
+            Error::CreateFileTree(err) => write!(f, "Failed to create file tree: {}", err),
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use crate::common::batch;
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_batch;
+use crate::common::drive_batch::Call;
 use crate::common::drive_file;
 use crate::common::hub_helper;
 use crate::files;
@@ -17,18 +20,103 @@ pub struct Config {
 pub async fn mv(config: Config) -> Result<(), Error> {
     let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
+    move_file(&hub, delegate_config, &config).await
+}
+
+// Moves each of the files to the folder of the config, the file id of the config is ignored.
+// The calls are sent with the batch api to keep the number of requests low
+pub async fn mv_many(
+    config: Config,
+    file_ids: Vec<String>,
+    concurrency: usize,
+) -> Result<(), batch::Error> {
+    let hub = hub_helper::get_hub().await.map_err(batch::Error::Hub)?;
+    let mut summary = batch::Summary::default();
+
+    // Every file would fail if the new parent is invalid, so check it before starting
+    let new_parent = files::info::get_file(&hub, &config.to_folder_id)
+        .await
+        .map_err(|err| batch::Error::InvalidOptions(Error::GetNewParent(err).to_string()))?;
+
+    err_if_not_directory(&new_parent)
+        .map_err(|err| batch::Error::InvalidOptions(err.to_string()))?;
+
+    let new_parent_name = new_parent.name.unwrap_or_default();
+
+    let get_calls: Vec<Call> = file_ids
+        .iter()
+        .map(|file_id| Call::get_file(file_id, "id,name,parents"))
+        .collect();
+
+    let get_results = drive_batch::execute(&hub, &get_calls, concurrency)
+        .await
+        .map_err(batch::Error::DriveBatch)?;
+
+    let mut files_to_move = vec![];
+
+    for (file_id, result) in file_ids.into_iter().zip(get_results) {
+        match drive_batch::parse::<google_drive3::api::File>(result) {
+            Ok(file) => match get_old_parent_id(&file) {
+                Ok(old_parent_id) => {
+                    files_to_move.push((file_id, file.name.unwrap_or_default(), old_parent_id))
+                }
+                Err(err) => summary.add_failure(&file_id, &err),
+            },
+
+            Err(err) => summary.add_failure(&file_id, &err),
+        }
+    }
 
-    let old_file = files::info::get_file(&hub, &config.file_id)
+    let move_calls = files_to_move
+        .iter()
+        .map(|(file_id, _, old_parent_id)| {
+            Call::update_file(
+                file_id,
+                &google_drive3::api::File::default(),
+                &[
+                    ("addParents", config.to_folder_id.as_str()),
+                    ("removeParents", old_parent_id.as_str()),
+                    ("fields", "id"),
+                ],
+            )
+        })
+        .collect::<Result<Vec<Call>, drive_batch::Error>>()
+        .map_err(batch::Error::DriveBatch)?;
+
+    let move_results = drive_batch::execute(&hub, &move_calls, concurrency)
+        .await
+        .map_err(batch::Error::DriveBatch)?;
+
+    for ((file_id, name, _), result) in files_to_move.into_iter().zip(move_results) {
+        match result {
+            Ok(_) => {
+                println!("Moved '{}' to '{}'", name, new_parent_name);
+                summary.add_success();
+            }
+
+            Err(err) => summary.add_failure(&file_id, &err),
+        }
+    }
+
+    summary.finish()
+}
+
+pub async fn move_file(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    config: &Config,
+) -> Result<(), Error> {
+    let old_file = files::info::get_file(hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
     let old_parent_id = get_old_parent_id(&old_file)?;
 
-    let old_parent = files::info::get_file(&hub, &old_parent_id)
+    let old_parent = files::info::get_file(hub, &old_parent_id)
         .await
         .map_err(|err| Error::GetOldParent(old_parent_id.clone(), err))?;
 
-    let new_parent = files::info::get_file(&hub, &config.to_folder_id)
+    let new_parent = files::info::get_file(hub, &config.to_folder_id)
         .await
         .map_err(Error::GetNewParent)?;
 
@@ -42,12 +130,12 @@ pub async fn mv(config: Config) -> Result<(), Error> {
     );
 
     let change_parent_config = ChangeParentConfig {
-        file_id: config.file_id,
+        file_id: config.file_id.clone(),
         old_parent_id,
-        new_parent_id: config.to_folder_id,
+        new_parent_id: config.to_folder_id.clone(),
     };
 
-    change_parent(&hub, delegate_config, &change_parent_config)
+    change_parent(hub, delegate_config, &change_parent_config)
         .await
         .map_err(Error::Move)?;
 
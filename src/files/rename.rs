@@ -1,7 +1,11 @@
+use crate::common::batch;
 use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_batch;
+use crate::common::drive_batch::Call;
 use crate::common::hub_helper;
 use crate::files;
 use crate::files::update::PatchFile;
+use crate::hub::Hub;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -15,8 +19,57 @@ pub struct Config {
 pub async fn rename(config: Config) -> Result<(), Error> {
     let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
+    rename_file(&hub, delegate_config, &config).await
+}
+
+// Renames files given as lines of the form '<file id> <new name>'.
+// The calls are sent with the batch api to keep the number of requests low
+pub async fn rename_many(lines: Vec<String>, concurrency: usize) -> Result<(), batch::Error> {
+    let renames = lines
+        .iter()
+        .map(|line| parse_rename_line(line))
+        .collect::<Result<Vec<(String, String)>, batch::Error>>()?;
+
+    let hub = hub_helper::get_hub().await.map_err(batch::Error::Hub)?;
+    let mut summary = batch::Summary::default();
+
+    let calls = renames
+        .iter()
+        .map(|(file_id, name)| {
+            let file = google_drive3::api::File {
+                name: Some(name.clone()),
+                ..google_drive3::api::File::default()
+            };
+
+            Call::update_file(file_id, &file, &[("fields", "id,name")])
+        })
+        .collect::<Result<Vec<Call>, drive_batch::Error>>()
+        .map_err(batch::Error::DriveBatch)?;
+
+    let results = drive_batch::execute(&hub, &calls, concurrency)
+        .await
+        .map_err(batch::Error::DriveBatch)?;
+
+    for ((file_id, name), result) in renames.into_iter().zip(results) {
+        match result {
+            Ok(_) => {
+                println!("Renamed {} to {}", file_id, name);
+                summary.add_success();
+            }
+
+            Err(err) => summary.add_failure(&file_id, &err),
+        }
+    }
 
-    let old_file = files::info::get_file(&hub, &config.file_id)
+    summary.finish()
+}
+
+pub async fn rename_file(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    config: &Config,
+) -> Result<(), Error> {
+    let old_file = files::info::get_file(hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
@@ -26,9 +79,9 @@ pub async fn rename(config: Config) -> Result<(), Error> {
         config.name
     );
 
-    let patch_file = PatchFile::new(config.file_id).with_name(&config.name);
+    let patch_file = PatchFile::new(config.file_id.clone()).with_name(&config.name);
 
-    files::update::update_metadata(&hub, delegate_config, patch_file)
+    files::update::update_metadata(hub, delegate_config, patch_file)
         .await
         .map_err(Error::Rename)?;
 
@@ -57,3 +110,19 @@ impl Display for Error {
         }
     }
 }
+
+fn parse_rename_line(line: &str) -> Result<(String, String), batch::Error> {
+    match line.split_once(char::is_whitespace) {
+        Some((file_id, name)) if !name.trim().is_empty() => {
+            Ok((file_id.to_string(), name.trim().to_string()))
+        }
+
+        _ => {
+            // fmt
+            Err(batch::Error::InvalidLine(
+                line.to_string(),
+                String::from("<file id> <new name>"),
+            ))
+        }
+    }
+}
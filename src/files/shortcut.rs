@@ -0,0 +1,133 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_file;
+use crate::common::drive_file::MIME_TYPE_DRIVE_SHORTCUT;
+use crate::common::hub_helper;
+use crate::files;
+use crate::hub::Hub;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+pub struct Config {
+    pub target_id: String,
+    // Defaults to the name of the target
+    pub name: Option<String>,
+    pub parents: Option<Vec<String>>,
+    pub print_only_id: bool,
+}
+
+pub async fn shortcut(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let target = files::info::get_file(&hub, &config.target_id)
+        .await
+        .map_err(Error::GetTarget)?;
+
+    // Drive doesn't allow shortcuts to shortcuts
+    if drive_file::is_shortcut(&target) {
+        return Err(Error::TargetIsShortcut(target.name.unwrap_or_default()));
+    }
+
+    let name = config
+        .name
+        .clone()
+        .or(target.name.clone())
+        .unwrap_or_default();
+
+    let file = create_shortcut(
+        &hub,
+        delegate_config,
+        &ShortcutConfig {
+            id: None,
+            target_id: config.target_id.clone(),
+            name: name.clone(),
+            parents: config.parents.clone(),
+        },
+    )
+    .await
+    .map_err(Error::CreateShortcut)?;
+
+    if config.print_only_id {
+        print!("{}", file.id.unwrap_or_default())
+    } else {
+        println!(
+            "Created shortcut '{}' to '{}' with id: {}",
+            name,
+            target.name.unwrap_or_default(),
+            file.id.unwrap_or_default()
+        );
+    }
+
+    Ok(())
+}
+
+pub struct ShortcutConfig {
+    // Pre-generated id, so a retried creation fails instead of creating a second shortcut
+    pub id: Option<String>,
+    pub target_id: String,
+    pub name: String,
+    pub parents: Option<Vec<String>>,
+}
+
+pub async fn create_shortcut(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    config: &ShortcutConfig,
+) -> Result<google_drive3::api::File, google_drive3::Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let dst_file = google_drive3::api::File {
+        id: config.id.clone(),
+        name: Some(config.name.clone()),
+        parents: config.parents.clone(),
+        mime_type: Some(MIME_TYPE_DRIVE_SHORTCUT.to_string()),
+        shortcut_details: Some(google_drive3::api::FileShortcutDetails {
+            target_id: Some(config.target_id.clone()),
+            ..google_drive3::api::FileShortcutDetails::default()
+        }),
+        ..google_drive3::api::File::default()
+    };
+
+    let (_, file) = hub
+        .files()
+        .create(dst_file)
+        .param("fields", "id,name,size,createdTime,modifiedTime,md5Checksum,mimeType,parents,shared,description,webContentLink,webViewLink,shortcutDetails(targetId,targetMimeType)")
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .supports_all_drives(true)
+        .doit_without_upload()
+        .await?;
+
+    Ok(file)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    GetTarget(google_drive3::Error),
+    TargetIsShortcut(String),
+    CreateShortcut(google_drive3::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::GetTarget(err) => write!(f, "Failed to get target file: {}", err),
+            Error::TargetIsShortcut(name) => {
+                write!(
+                    f,
+                    "'{}' is a shortcut, shortcuts can't point to other shortcuts",
+                    name
+                )
+            }
+            Error::CreateShortcut(err) => {
+                write!(f, "Failed to create shortcut on drive: {}", err)
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::common::drive_file;
+use crate::common::file_tree_drive;
+use crate::common::file_tree_drive::FileTreeDrive;
+use crate::common::file_tree_drive::Node;
+use crate::common::file_tree_drive::TreeOptions;
+use crate::common::hub_helper;
+use crate::files;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+pub struct Config {
+    pub folder_id: String,
+    pub max_depth: Option<usize>,
+}
+
+pub async fn tree(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+
+    let file = files::info::get_file(&hub, &config.folder_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    if !drive_file::is_directory(&file) {
+        return Err(Error::NotADirectory(file.name.unwrap_or_default()));
+    }
+
+    let tree = FileTreeDrive::from_file_with_options(
+        &hub,
+        &file,
+        &TreeOptions {
+            include_documents: true,
+            max_depth: config.max_depth,
+        },
+    )
+    .await
+    .map_err(Error::CreateFileTree)?;
+
+    let mut counts = Counts::default();
+
+    println!("{}", tree.root.name);
+    print_children(&tree.root, "", &mut counts);
+
+    println!();
+    println!(
+        "{} directories, {} files",
+        counts.folder_count, counts.file_count
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    folder_count: usize,
+    file_count: usize,
+}
+
+fn print_children(folder: &file_tree_drive::Folder, prefix: &str, counts: &mut Counts) {
+    let children = folder.sorted_children();
+    let last_index = children.len().saturating_sub(1);
+
+    for (index, child) in children.iter().enumerate() {
+        let is_last = index == last_index;
+        let connector = if is_last { "└── " } else { "├── " };
+
+        println!("{}{}{}", prefix, connector, child.name());
+
+        match child {
+            Node::FolderNode(child_folder) => {
+                counts.folder_count += 1;
+
+                let child_prefix = if is_last {
+                    format!("{}    ", prefix)
+                } else {
+                    format!("{}│   ", prefix)
+                };
+
+                print_children(child_folder, &child_prefix, counts);
+            }
+
+            Node::FileNode(_) => {
+                counts.file_count += 1;
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    GetFile(google_drive3::Error),
+    NotADirectory(String),
+    CreateFileTree(file_tree_drive::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::GetFile(err) => write!(f, "Failed getting file: {}", err),
+            Error::NotADirectory(name) => write!(f, "'{}' is not a directory", name),
+            Error::CreateFileTree(err) => write!(f, "Failed to create file tree: {}", err),
+        }
+    }
+}
@@ -91,6 +91,7 @@ where
 {
     let dst_file = google_drive3::api::File {
         name: Some(file_info.name),
+        modified_time: file_info.modified_time,
         ..google_drive3::api::File::default()
     };
 
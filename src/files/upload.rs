@@ -2,19 +2,26 @@ use crate::common::delegate::BackoffConfig;
 use crate::common::delegate::ChunkSize;
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
+use crate::common::failure_report;
+use crate::common::failure_report::FailureReport;
 use crate::common::file_info;
 use crate::common::file_info::FileInfo;
 use crate::common::file_tree;
 use crate::common::file_tree::FileTree;
+use crate::common::file_tree::SymlinkAction;
 use crate::common::file_helper;
 use crate::common::hub_helper;
 use crate::common::id_gen::IdGen;
+use crate::common::upload_journal;
+use crate::common::upload_journal::UploadJournal;
 use crate::files;
 use crate::files::info::DisplayConfig;
 use crate::files::mkdir;
+use crate::files::shortcut;
 use crate::hub::Hub;
 use human_bytes::human_bytes;
 use mime::Mime;
+use std::collections::HashSet;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -32,6 +39,13 @@ pub struct Config {
     pub print_chunk_info: bool,
     pub upload_directories: bool,
     pub print_only_id: bool,
+    pub symlinks: SymlinkAction,
+    pub keep_going: bool,
+    pub failure_report: Option<PathBuf>,
+    pub journal: Option<PathBuf>,
+    pub resume: Option<PathBuf>,
+    // Send the local creation time, drive uses the upload time otherwise
+    pub preserve_created_time: bool,
 }
 
 pub async fn upload(config: Config) -> Result<(), Error> {
@@ -48,12 +62,34 @@ pub async fn upload(config: Config) -> Result<(), Error> {
         print_chunk_info: config.print_chunk_info,
     };
 
+    if let Some(journal_path) = config.resume.clone() {
+        let journal = UploadJournal::open(&journal_path).map_err(Error::Journal)?;
+
+        let config = Config {
+            file_path: Some(journal.root_path()),
+            parents: journal.parents(),
+            ..config
+        };
+
+        return upload_directory(&hub, &config, Some(journal), delegate_config)
+            .await
+            .map(|_| ());
+    }
+
     match &config.file_path {
         Some(path) => {
             err_if_directory(&path, &config)?;
 
             if path.is_dir() {
-                upload_directory(&hub, &config, delegate_config).await?;
+                let journal = match &config.journal {
+                    Some(journal_path) => Some(
+                        UploadJournal::create(journal_path, path, &config.parents)
+                            .map_err(Error::Journal)?,
+                    ),
+                    None => None,
+                };
+
+                upload_directory(&hub, &config, journal, delegate_config).await?;
             } else {
                 upload_regular(&hub, &config, delegate_config).await?;
             }
@@ -81,7 +117,7 @@ pub async fn upload_regular(
     let file = fs::File::open(file_path)
         .map_err(|err| Error::OpenFile(file_path.clone(), err))?;
 
-    let file_info = FileInfo::from_file(
+    let mut file_info = FileInfo::from_file(
         &file,
         &file_info::Config {
             file_path: file_path.clone(),
@@ -91,6 +127,14 @@ pub async fn upload_regular(
     )
     .map_err(Error::FileInfo)?;
 
+    if config.preserve_created_time {
+        file_info.created_time = file
+            .metadata()
+            .ok()
+            .as_ref()
+            .and_then(file_info::created_time);
+    }
+
     let reader = std::io::BufReader::new(file);
 
     if !config.print_only_id {
@@ -115,12 +159,26 @@ pub async fn upload_regular(
 pub async fn upload_directory(
     hub: &Hub,
     config: &Config,
+    mut journal: Option<UploadJournal>,
     delegate_config: UploadDelegateConfig,
-) -> Result<(), Error> {
-    let mut ids = IdGen::new(hub, &delegate_config);
-    let tree = FileTree::from_path(config.file_path.as_ref().unwrap(), &mut ids)
-        .await
-        .map_err(Error::CreateFileTree)?;
+) -> Result<FileTree, Error> {
+    let reserved_ids = journal
+        .as_ref()
+        .map(|journal| journal.known_ids())
+        .unwrap_or_default();
+
+    let mut ids = IdGen::new(hub, &delegate_config).with_reserved_ids(reserved_ids);
+    let tree = FileTree::from_path(
+        config.file_path.as_ref().unwrap(),
+        config.symlinks,
+        &mut ids,
+    )
+    .await
+    .map_err(Error::CreateFileTree)?;
+
+    if let Some(journal) = &mut journal {
+        journal.add_tree(&tree).map_err(Error::Journal)?;
+    }
 
     let tree_info = tree.info();
 
@@ -133,71 +191,130 @@ pub async fn upload_directory(
         );
     }
 
+    let mut report = FailureReport::new();
+    let mut failed_folder_ids: HashSet<String> = HashSet::new();
+
     for folder in &tree.folders() {
-        let folder_parents = folder
+        let parent_failed = folder
             .parent
             .as_ref()
-            .map(|p| vec![p.drive_id.clone()])
-            .or_else(|| config.parents.clone());
-
-        if !config.print_only_id {
-            println!(
-                "Creating directory '{}' with id: {}",
-                folder.relative_path().display(),
-                folder.drive_id
-            );
-        }
+            .map(|p| failed_folder_ids.contains(&p.drive_id))
+            .unwrap_or(false);
+
+        let folder_result = if parent_failed {
+            Err(Error::ParentDirectoryFailed)
+        } else if is_completed(&journal, &folder.path) {
+            print_already_uploaded(config, &folder.relative_path());
+            Ok(folder.drive_id.clone())
+        } else {
+            let result = create_tree_folder(hub, config, folder, delegate_config.clone()).await;
+
+            match recover_existing_item(hub, &journal, &folder.drive_id, result).await {
+                Ok(Recovery::Existing) => {
+                    print_already_uploaded(config, &folder.relative_path());
+                    Ok(folder.drive_id.clone())
+                }
+                Ok(Recovery::Created) => Ok(folder.drive_id.clone()),
+                Err(err) => Err(err),
+            }
+        };
 
-        let drive_folder = mkdir::create_directory(
-            hub,
-            &mkdir::Config {
-                id: Some(folder.drive_id.clone()),
-                name: folder.name.clone(),
-                parents: folder_parents,
-                print_only_id: false,
-            },
-            delegate_config.clone(),
-        )
-        .await
-        .map_err(Error::Mkdir)?;
+        let folder_id = match folder_result {
+            Ok(id) => {
+                mark_completed(&mut journal, &folder.path)?;
+                id
+            }
 
-        if config.print_only_id {
-            println!("{}: {}", folder.relative_path().display(), folder.drive_id);
-        }
+            Err(err) if config.keep_going => {
+                report.add(&folder.relative_path(), &err);
+                failed_folder_ids.insert(folder.drive_id.clone());
 
-        let folder_id = drive_folder.id.ok_or(Error::DriveFolderMissingId)?;
-        let parents = Some(vec![folder_id.clone()]);
+                for file in folder.files() {
+                    report.add(&file.relative_path(), &Error::ParentDirectoryFailed);
+                }
 
-        for file in folder.files() {
-            let os_file = fs::File::open(&file.path)
-                .map_err(|err| Error::OpenFile(config.file_path.as_ref().unwrap().clone(), err))?;
+                continue;
+            }
 
-            let file_info = file.info(parents.clone());
+            Err(err) => return Err(err),
+        };
 
-            if !config.print_only_id {
-                println!(
-                    "Uploading file '{}' with id: {}",
-                    file.relative_path().display(),
-                    file.drive_id
-                );
+        let parents = Some(vec![folder_id]);
+
+        for file in folder.files() {
+            let result = if is_completed(&journal, &file.path) {
+                update_changed_tree_file(hub, config, &mut journal, &file, delegate_config.clone())
+                    .await
+            } else {
+                let result =
+                    upload_tree_file(hub, config, &file, &parents, delegate_config.clone()).await;
+
+                match recover_existing_item(hub, &journal, &file.drive_id, result).await {
+                    // The file may have changed locally since it was uploaded
+                    Ok(Recovery::Existing) => {
+                        update_changed_tree_file(
+                            hub,
+                            config,
+                            &mut journal,
+                            &file,
+                            delegate_config.clone(),
+                        )
+                        .await
+                    }
+                    Ok(Recovery::Created) => Ok(()),
+                    Err(err) => Err(err),
+                }
+            };
+
+            match result {
+                Ok(()) => mark_completed(&mut journal, &file.path)?,
+                Err(err) if config.keep_going => report.add(&file.relative_path(), &err),
+                Err(err) => return Err(err),
             }
+        }
+    }
 
-            upload_file(
-                hub,
-                os_file,
-                Some(file.drive_id.clone()),
-                file_info,
-                delegate_config.clone(),
-            )
-            .await
-            .map_err(Error::Upload)?;
+    // Shortcuts are created last to make sure that their targets exist
+    for shortcut in tree.shortcuts() {
+        if failed_folder_ids.contains(&shortcut.parent.drive_id) {
+            report.add(&shortcut.relative_path(), &Error::ParentDirectoryFailed);
+            continue;
+        }
+
+        if is_completed(&journal, &shortcut.path) {
+            print_already_uploaded(config, &shortcut.relative_path());
+            continue;
+        }
 
-            if config.print_only_id {
-                println!("{}: {}", file.relative_path().display(), file.drive_id);
+        let result =
+            create_tree_shortcut(hub, config, &tree, &shortcut, delegate_config.clone()).await;
+
+        let result = match recover_existing_item(hub, &journal, &shortcut.drive_id, result).await {
+            Ok(Recovery::Existing) => {
+                print_already_uploaded(config, &shortcut.relative_path());
+                Ok(())
             }
+            Ok(Recovery::Created) => Ok(()),
+            Err(err) => Err(err),
+        };
+
+        match result {
+            Ok(()) => mark_completed(&mut journal, &shortcut.path)?,
+            Err(err) if config.keep_going => report.add(&shortcut.relative_path(), &err),
+            Err(err) => return Err(err),
         }
     }
 
+    if !report.is_empty() {
+        report.print_summary();
+
+        if let Some(path) = &config.failure_report {
+            report.save(path).map_err(Error::SaveFailureReport)?;
+        }
+
+        return Err(Error::PartialFailure(report.len()));
+    }
+
     if !config.print_only_id {
         println!(
             "Uploaded {} files in {} directories with a total size of {}",
@@ -207,6 +324,184 @@ pub async fn upload_directory(
         );
     }
 
+    Ok(tree)
+}
+
+async fn create_tree_folder(
+    hub: &Hub,
+    config: &Config,
+    folder: &file_tree::Folder,
+    delegate_config: UploadDelegateConfig,
+) -> Result<String, Error> {
+    let folder_parents = folder
+        .parent
+        .as_ref()
+        .map(|p| vec![p.drive_id.clone()])
+        .or_else(|| config.parents.clone());
+
+    if !config.print_only_id {
+        println!(
+            "Creating directory '{}' with id: {}",
+            folder.relative_path().display(),
+            folder.drive_id
+        );
+    }
+
+    let drive_folder = mkdir::create_directory(
+        hub,
+        &mkdir::Config {
+            id: Some(folder.drive_id.clone()),
+            name: folder.name.clone(),
+            parents: folder_parents,
+            print_only_id: false,
+        },
+        delegate_config,
+    )
+    .await
+    .map_err(Error::Mkdir)?;
+
+    if config.print_only_id {
+        println!("{}: {}", folder.relative_path().display(), folder.drive_id);
+    }
+
+    drive_folder.id.ok_or(Error::DriveFolderMissingId)
+}
+
+async fn upload_tree_file(
+    hub: &Hub,
+    config: &Config,
+    file: &file_tree::File,
+    parents: &Option<Vec<String>>,
+    delegate_config: UploadDelegateConfig,
+) -> Result<(), Error> {
+    let os_file =
+        fs::File::open(&file.path).map_err(|err| Error::OpenFile(file.path.clone(), err))?;
+
+    let mut file_info = file.info(parents.clone());
+
+    if !config.preserve_created_time {
+        file_info.created_time = None;
+    }
+
+    if !config.print_only_id {
+        println!(
+            "Uploading file '{}' with id: {}",
+            file.relative_path().display(),
+            file.drive_id
+        );
+    }
+
+    upload_file(
+        hub,
+        os_file,
+        Some(file.drive_id.clone()),
+        file_info,
+        delegate_config,
+    )
+    .await
+    .map_err(Error::Upload)?;
+
+    if config.print_only_id {
+        println!("{}: {}", file.relative_path().display(), file.drive_id);
+    }
+
+    Ok(())
+}
+
+// Files that were uploaded in a previous run are only uploaded again if they have changed since
+async fn update_changed_tree_file(
+    hub: &Hub,
+    config: &Config,
+    journal: &mut Option<UploadJournal>,
+    file: &file_tree::File,
+    delegate_config: UploadDelegateConfig,
+) -> Result<(), Error> {
+    let journal = match journal {
+        Some(journal) => journal,
+        None => return Ok(()),
+    };
+
+    let has_changed = journal
+        .entry(&file.path)
+        .map(|entry| upload_journal::has_changed(entry, file))
+        .unwrap_or(false);
+
+    if !has_changed {
+        print_already_uploaded(config, &file.relative_path());
+        return Ok(());
+    }
+
+    let os_file =
+        fs::File::open(&file.path).map_err(|err| Error::OpenFile(file.path.clone(), err))?;
+
+    if !config.print_only_id {
+        println!(
+            "Updating changed file '{}' with id: {}",
+            file.relative_path().display(),
+            file.drive_id
+        );
+    }
+
+    files::update::update_file(
+        hub,
+        os_file,
+        &file.drive_id,
+        file.info(None),
+        delegate_config,
+    )
+    .await
+    .map_err(Error::Upload)?;
+
+    journal.update_file(file).map_err(Error::Journal)
+}
+
+async fn create_tree_shortcut(
+    hub: &Hub,
+    config: &Config,
+    tree: &FileTree,
+    shortcut: &file_tree::Shortcut,
+    delegate_config: UploadDelegateConfig,
+) -> Result<(), Error> {
+    let target_id = match tree.find_drive_id(&shortcut.target) {
+        Some(id) => id,
+        None => {
+            eprintln!(
+                "Warning: Skipping symlink '{}', the target is outside of the uploaded directory",
+                shortcut.relative_path().display()
+            );
+            return Ok(());
+        }
+    };
+
+    if !config.print_only_id {
+        println!(
+            "Creating shortcut '{}' to {}",
+            shortcut.relative_path().display(),
+            target_id
+        );
+    }
+
+    let drive_shortcut = shortcut::create_shortcut(
+        hub,
+        delegate_config,
+        &shortcut::ShortcutConfig {
+            id: Some(shortcut.drive_id.clone()),
+            target_id,
+            name: shortcut.name.clone(),
+            parents: Some(vec![shortcut.parent.drive_id.clone()]),
+        },
+    )
+    .await
+    .map_err(Error::CreateShortcut)?;
+
+    if config.print_only_id {
+        println!(
+            "{}: {}",
+            shortcut.relative_path().display(),
+            drive_shortcut.id.unwrap_or_default()
+        );
+    }
+
     Ok(())
 }
 
@@ -225,6 +520,8 @@ where
         name: Some(file_info.name),
         mime_type: Some(file_info.mime_type.to_string()),
         parents: file_info.parents,
+        modified_time: file_info.modified_time,
+        created_time: file_info.created_time,
         ..google_drive3::api::File::default()
     };
 
@@ -248,6 +545,64 @@ where
     Ok(file)
 }
 
+enum Recovery {
+    Created,
+    Existing,
+}
+
+// Resumed uploads reuse the ids of the previous run. Drive rejects them with a conflict if
+// the item was created before the process was interrupted, but not marked as completed
+async fn recover_existing_item<T>(
+    hub: &Hub,
+    journal: &Option<UploadJournal>,
+    drive_id: &str,
+    result: Result<T, Error>,
+) -> Result<Recovery, Error> {
+    let err = match result {
+        Ok(_) => return Ok(Recovery::Created),
+        Err(err) if journal.is_some() && is_conflict(&err) => err,
+        Err(err) => return Err(err),
+    };
+
+    files::info::get_file(hub, drive_id)
+        .await
+        .map_err(|_| err)?;
+
+    Ok(Recovery::Existing)
+}
+
+fn is_conflict(err: &Error) -> bool {
+    match err {
+        Error::Mkdir(err) | Error::Upload(err) | Error::CreateShortcut(err) => {
+            hub_helper::error_status(err) == Some(409)
+        }
+        _ => false,
+    }
+}
+
+fn is_completed(journal: &Option<UploadJournal>, path: &PathBuf) -> bool {
+    journal
+        .as_ref()
+        .map(|journal| journal.is_completed(path))
+        .unwrap_or(false)
+}
+
+fn mark_completed(journal: &mut Option<UploadJournal>, path: &PathBuf) -> Result<(), Error> {
+    match journal {
+        Some(journal) => journal.mark_completed(path).map_err(Error::Journal),
+        None => Ok(()),
+    }
+}
+
+fn print_already_uploaded(config: &Config, relative_path: &PathBuf) {
+    if !config.print_only_id {
+        println!(
+            "Skipping '{}', it was uploaded in a previous run",
+            relative_path.display()
+        );
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     Hub(hub_helper::Error),
@@ -258,6 +613,20 @@ pub enum Error {
     DriveFolderMissingId,
     CreateFileTree(file_tree::Error),
     Mkdir(google_drive3::Error),
+    CreateShortcut(google_drive3::Error),
+    ParentDirectoryFailed,
+    SaveFailureReport(failure_report::Error),
+    PartialFailure(usize),
+    Journal(upload_journal::Error),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::PartialFailure(_) => failure_report::PARTIAL_FAILURE_EXIT_CODE,
+            _ => 1,
+        }
+    }
 }
 
 impl error::Error for Error {}
@@ -279,6 +648,13 @@ impl Display for Error {
             Error::DriveFolderMissingId => write!(f, "Folder created on drive does not have an id"),
             Error::CreateFileTree(err) => write!(f, "Failed to create file tree: {}", err),
             Error::Mkdir(err) => write!(f, "Failed to create directory: {}", err),
+            Error::CreateShortcut(err) => write!(f, "Failed to create shortcut: {}", err),
+            Error::ParentDirectoryFailed => {
+                write!(f, "Skipped because the parent directory was not created")
+            }
+            Error::SaveFailureReport(err) => write!(f, "{}", err),
+            Error::PartialFailure(count) => write!(f, "Failed to upload {} items", count),
+            Error::Journal(err) => write!(f, "{}", err),
         }
     }
 }
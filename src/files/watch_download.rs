@@ -0,0 +1,477 @@
+use crate::app_config;
+use crate::app_config::AppConfig;
+use crate::changes::list;
+use crate::changes::list::ListChangesConfig;
+use crate::changes::start_token;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_file;
+use crate::common::existing_file::ExistingFileAction;
+use crate::common::file_tree_drive::FileTreeDrive;
+use crate::common::hub_helper;
+use crate::common::local_name;
+use crate::files;
+use crate::files::download;
+use crate::hub::Hub;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub struct Config {
+    pub folder_id: String,
+    pub local_dir: PathBuf,
+    pub interval: Duration,
+}
+
+// The state is saved after each poll, a restarted mirror only applies the changes made since
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MirrorState {
+    root_path: PathBuf,
+    page_token: String,
+    items: HashMap<String, MirrorItem>,
+}
+
+// Local path of a drive folder or file, relative to the root path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MirrorItem {
+    path: PathBuf,
+    is_folder: bool,
+}
+
+pub async fn watch_download(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let app_cfg = AppConfig::load_current_account().map_err(Error::AppConfig)?;
+    let state_path = app_cfg.changes_state_path(&format!("download_{}", config.folder_id));
+
+    if !config.local_dir.is_dir() {
+        return Err(Error::NotLocalDirectory(config.local_dir.clone()));
+    }
+
+    let root_path = config
+        .local_dir
+        .canonicalize()
+        .map_err(|err| Error::CanonicalizePath(config.local_dir.clone(), err))?;
+
+    let state = match load_state(&state_path)? {
+        Some(state) if state.root_path == root_path => Some(state),
+
+        Some(state) => {
+            eprintln!(
+                "Warning: The folder was mirrored to '{}' before, starting over with '{}'",
+                state.root_path.display(),
+                root_path.display()
+            );
+            None
+        }
+
+        None => None,
+    };
+
+    let state = match state {
+        Some(state) => state,
+        None => initial_mirror(&hub, delegate_config.clone(), &config, &root_path).await?,
+    };
+
+    save_state(&state_path, &state)?;
+
+    let mut mirror = Mirror {
+        hub: &hub,
+        folder_id: config.folder_id.clone(),
+        state,
+    };
+
+    println!("Watching {} for changes", config.folder_id);
+
+    loop {
+        tokio::time::sleep(config.interval).await;
+
+        let list_config = ListChangesConfig {
+            page_token: mirror.state.page_token.clone(),
+            drive_id: None,
+        };
+
+        // Failures to reach drive are retried on the next poll
+        let changes = match list::list_changes(&hub, delegate_config.clone(), &list_config).await {
+            Ok(changes) => changes,
+            Err(err) => {
+                eprintln!("Warning: {}", err);
+                continue;
+            }
+        };
+
+        let mut failed = 0;
+
+        for change in &changes.changes {
+            if let Err(err) = mirror.apply_change(change).await {
+                eprintln!(
+                    "Warning: Failed to apply change of {}: {}",
+                    change.file_id.clone().unwrap_or_default(),
+                    err
+                );
+                failed += 1;
+            }
+        }
+
+        // The same changes are applied again on the next poll if any of them failed
+        if failed == 0 {
+            mirror.state.page_token = changes.new_start_page_token;
+        }
+
+        save_state(&state_path, &mirror.state)?;
+    }
+}
+
+// The start token is taken before downloading to not miss changes made during the download
+async fn initial_mirror(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    config: &Config,
+    root_path: &PathBuf,
+) -> Result<MirrorState, Error> {
+    let folder = files::info::get_file(hub, &config.folder_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    if !drive_file::is_directory(&folder) {
+        return Err(Error::NotDirectory(folder.name.unwrap_or_default()));
+    }
+
+    let page_token = start_token::get_start_page_token(hub, delegate_config, &None)
+        .await
+        .map_err(Error::StartToken)?;
+
+    let tree = download::download_directory(hub, &folder, &download_config(root_path))
+        .await
+        .map_err(Error::Download)?;
+
+    Ok(MirrorState {
+        root_path: root_path.clone(),
+        page_token,
+        items: tree_items(&tree, &PathBuf::new()),
+    })
+}
+
+struct Mirror<'a> {
+    hub: &'a Hub,
+    folder_id: String,
+    state: MirrorState,
+}
+
+impl<'a> Mirror<'a> {
+    async fn apply_change(&mut self, change: &google_drive3::api::Change) -> Result<(), Error> {
+        let file_id = match &change.file_id {
+            Some(file_id) if file_id != &self.folder_id => file_id.clone(),
+            _ => return Ok(()),
+        };
+
+        let file = change.file.clone().unwrap_or_default();
+        let is_removed = change.removed.unwrap_or(false) || file.trashed.unwrap_or(false);
+
+        let new_path = if is_removed {
+            None
+        } else {
+            self.local_path(&file_id, &file)
+        };
+
+        match (self.state.items.get(&file_id).cloned(), new_path) {
+            // Not in the mirrored folder before or after the change
+            (None, None) => Ok(()),
+
+            // Trashed, removed or moved out of the mirrored folder
+            (Some(item), None) => self.remove(&item),
+
+            (old_item, Some(path)) => {
+                if let Some(item) = &old_item {
+                    if item.path != path {
+                        self.rename(item, &path)?;
+                    }
+                }
+
+                if !drive_file::is_directory(&file) {
+                    self.download_file(&file_id, &file, &path).await
+                } else if old_item.is_none() {
+                    self.download_folder(&file, &path).await
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    // Local path for the file if its parent is part of the mirror. Documents and
+    // shortcuts are skipped like in recursive downloads
+    fn local_path(&self, file_id: &str, file: &google_drive3::api::File) -> Option<PathBuf> {
+        if !drive_file::is_directory(file) && !drive_file::is_binary(file) {
+            return None;
+        }
+
+        let parent_path = file
+            .parents
+            .as_ref()?
+            .iter()
+            .filter_map(|parent_id| self.state.items.get(parent_id))
+            .find(|item| item.is_folder)
+            .map(|item| item.path.clone())?;
+
+        let name = local_name::sanitize(&file.name.clone().unwrap_or_default());
+        let path = parent_path.join(&name);
+
+        let is_taken = self
+            .state
+            .items
+            .iter()
+            .any(|(id, item)| id != file_id && item.path == path);
+
+        if is_taken {
+            Some(parent_path.join(local_name::disambiguate(&name, file_id)))
+        } else {
+            Some(path)
+        }
+    }
+
+    fn remove(&mut self, item: &MirrorItem) -> Result<(), Error> {
+        let abs_path = self.state.root_path.join(&item.path);
+
+        println!("Removing '{}'", item.path.display());
+
+        let result = if item.is_folder {
+            fs::remove_dir_all(&abs_path)
+        } else {
+            fs::remove_file(&abs_path)
+        };
+
+        match result {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                return Err(Error::Remove(abs_path, err));
+            }
+
+            _ => {}
+        }
+
+        self.state
+            .items
+            .retain(|_, other| !other.path.starts_with(&item.path));
+
+        Ok(())
+    }
+
+    fn rename(&mut self, item: &MirrorItem, new_path: &PathBuf) -> Result<(), Error> {
+        let abs_old_path = self.state.root_path.join(&item.path);
+        let abs_new_path = self.state.root_path.join(new_path);
+
+        if abs_old_path.exists() {
+            println!(
+                "Moving '{}' to '{}'",
+                item.path.display(),
+                new_path.display()
+            );
+
+            if let Some(parent) = abs_new_path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|err| Error::CreateDirectory(parent.to_path_buf(), err))?;
+            }
+
+            fs::rename(&abs_old_path, &abs_new_path)
+                .map_err(|err| Error::Move(abs_old_path.clone(), err))?;
+        }
+
+        // Items in a moved folder move with it
+        for other in self.state.items.values_mut() {
+            if let Ok(relative) = other.path.strip_prefix(&item.path) {
+                other.path = if relative.as_os_str().is_empty() {
+                    new_path.clone()
+                } else {
+                    new_path.join(relative)
+                };
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn download_file(
+        &mut self,
+        file_id: &str,
+        file: &google_drive3::api::File,
+        path: &PathBuf,
+    ) -> Result<(), Error> {
+        let abs_path = self.state.root_path.join(path);
+
+        if !download::local_file_is_identical(&abs_path, &file.md5_checksum) {
+            if let Some(parent) = abs_path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|err| Error::CreateDirectory(parent.to_path_buf(), err))?;
+            }
+
+            let body = download::download_file(self.hub, file_id)
+                .await
+                .map_err(Error::DownloadFile)?;
+
+            println!("Downloading file '{}'", path.display());
+
+            download::save_body_to_file(
+                body,
+                &abs_path,
+                file.md5_checksum.clone(),
+                file.modified_time,
+            )
+            .await
+            .map_err(Error::Download)?;
+        }
+
+        let item = MirrorItem {
+            path: path.clone(),
+            is_folder: false,
+        };
+
+        self.state.items.insert(file_id.to_string(), item);
+        Ok(())
+    }
+
+    // A new folder, or a folder that was moved into the mirror, is downloaded with its contents
+    async fn download_folder(
+        &mut self,
+        file: &google_drive3::api::File,
+        path: &PathBuf,
+    ) -> Result<(), Error> {
+        let parent_path = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        let abs_parent_path = self.state.root_path.join(&parent_path);
+
+        let tree = download::download_directory(self.hub, file, &download_config(&abs_parent_path))
+            .await
+            .map_err(Error::Download)?;
+
+        self.state.items.extend(tree_items(&tree, &parent_path));
+        Ok(())
+    }
+}
+
+fn download_config(destination: &PathBuf) -> download::Config {
+    download::Config {
+        file_id: String::new(),
+        existing_file_action: ExistingFileAction::Overwrite,
+        follow_shortcuts: false,
+        download_directories: true,
+        destination: download::Destination::Path(destination.clone()),
+        manifest_path: None,
+        keep_going: false,
+        failure_report: None,
+    }
+}
+
+fn tree_items(tree: &FileTreeDrive, base_path: &PathBuf) -> HashMap<String, MirrorItem> {
+    let mut items = HashMap::new();
+
+    for folder in tree.folders() {
+        let item = MirrorItem {
+            path: base_path.join(folder.relative_path()),
+            is_folder: true,
+        };
+        items.insert(folder.drive_id.clone(), item);
+
+        for file in folder.files() {
+            let item = MirrorItem {
+                path: base_path.join(file.relative_path()),
+                is_folder: false,
+            };
+            items.insert(file.drive_id.clone(), item);
+        }
+    }
+
+    items
+}
+
+fn load_state(path: &PathBuf) -> Result<Option<MirrorState>, Error> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path).map_err(|err| Error::ReadState(path.clone(), err))?;
+
+    let state = serde_json::from_str(&content).map_err(Error::DeserializeState)?;
+    Ok(Some(state))
+}
+
+fn save_state(path: &PathBuf, state: &MirrorState) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| Error::WriteState(path.clone(), err))?;
+    }
+
+    let content = serde_json::to_string_pretty(state).map_err(Error::SerializeState)?;
+    fs::write(path, content).map_err(|err| Error::WriteState(path.clone(), err))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    AppConfig(app_config::Error),
+    GetFile(google_drive3::Error),
+    NotDirectory(String),
+    NotLocalDirectory(PathBuf),
+    CanonicalizePath(PathBuf, io::Error),
+    StartToken(start_token::Error),
+    Download(download::Error),
+    DownloadFile(google_drive3::Error),
+    CreateDirectory(PathBuf, io::Error),
+    Move(PathBuf, io::Error),
+    Remove(PathBuf, io::Error),
+    ReadState(PathBuf, io::Error),
+    WriteState(PathBuf, io::Error),
+    SerializeState(serde_json::Error),
+    DeserializeState(serde_json::Error),
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::AppConfig(err) => write!(f, "{}", err),
+            Error::GetFile(err) => write!(f, "Failed to get file: {}", err),
+            Error::NotDirectory(name) => write!(f, "'{}' is not a directory", name),
+            Error::NotLocalDirectory(path) => {
+                write!(f, "'{}' is not a local directory", path.display())
+            }
+            Error::CanonicalizePath(path, err) => write!(
+                f,
+                "Failed to get canonical path of {}: {}",
+                path.display(),
+                err
+            ),
+            Error::StartToken(err) => write!(f, "{}", err),
+            Error::Download(err) => write!(f, "{}", err),
+            Error::DownloadFile(err) => write!(f, "Failed to download file: {}", err),
+            Error::CreateDirectory(path, err) => write!(
+                f,
+                "Failed to create directory '{}': {}",
+                path.display(),
+                err
+            ),
+            Error::Move(path, err) => write!(f, "Failed to move '{}': {}", path.display(), err),
+            Error::Remove(path, err) => {
+                write!(f, "Failed to remove '{}': {}", path.display(), err)
+            }
+            Error::ReadState(path, err) => {
+                write!(f, "Failed to read '{}': {}", path.display(), err)
+            }
+            Error::WriteState(path, err) => {
+                write!(f, "Failed to write '{}': {}", path.display(), err)
+            }
+            Error::SerializeState(err) => write!(f, "Failed to serialize mirror state: {}", err),
+            Error::DeserializeState(err) => {
+                write!(f, "Failed to deserialize mirror state: {}", err)
+            }
+        }
+    }
+}
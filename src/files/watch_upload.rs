@@ -0,0 +1,772 @@
+use crate::common::delegate::BackoffConfig;
+use crate::common::delegate::ChunkSize;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::file_info;
+use crate::common::file_info::FileInfo;
+use crate::common::file_tree::FileTree;
+use crate::common::file_tree::SymlinkAction;
+use crate::common::hub_helper;
+use crate::common::upload_journal;
+use crate::common::upload_journal::EntryKind;
+use crate::common::upload_journal::JournalEntry;
+use crate::common::upload_journal::UploadJournal;
+use crate::files;
+use crate::files::mkdir;
+use crate::files::mv;
+use crate::files::rename;
+use crate::files::upload;
+use crate::hub::Hub;
+use notify::event::AccessKind;
+use notify::event::AccessMode;
+use notify::event::CreateKind;
+use notify::event::ModifyKind;
+use notify::event::RenameMode;
+use notify::Event;
+use notify::EventKind;
+use notify::RecursiveMode;
+use notify::Watcher;
+use std::collections::HashMap;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+// How often the pending changes are checked when there are no filesystem events
+const TICK: Duration = Duration::from_millis(250);
+
+// Only inotify reports when a file that was opened for writing is closed,
+// on other platforms the changes are uploaded when the debounce period has passed
+const WAITS_FOR_CLOSE: bool = cfg!(target_os = "linux");
+
+pub struct Config {
+    pub path: PathBuf,
+    pub parents: Option<Vec<String>>,
+    pub debounce: Duration,
+    pub symlinks: SymlinkAction,
+    pub journal: Option<PathBuf>,
+    pub chunk_size: ChunkSize,
+    pub print_chunk_errors: bool,
+    pub print_chunk_info: bool,
+}
+
+pub async fn watch_upload(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+
+    let delegate_config = UploadDelegateConfig {
+        chunk_size: config.chunk_size.clone(),
+        backoff_config: BackoffConfig {
+            max_retries: 100000,
+            min_sleep: Duration::from_secs(1),
+            max_sleep: Duration::from_secs(60),
+        },
+        print_chunk_errors: config.print_chunk_errors,
+        print_chunk_info: config.print_chunk_info,
+    };
+
+    let root_path = config
+        .path
+        .canonicalize()
+        .map_err(|err| Error::CanonicalizePath(config.path.clone(), err))?;
+
+    if !root_path.is_dir() {
+        return Err(Error::NotDirectory(config.path.clone()));
+    }
+
+    // The watcher is started before the initial upload to not miss changes made while it runs
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = sender.send(event);
+    })
+    .map_err(Error::Watch)?;
+
+    watcher
+        .watch(&root_path, RecursiveMode::Recursive)
+        .map_err(Error::Watch)?;
+
+    let journal = open_journal(&config, &root_path)?;
+
+    let upload_config = upload::Config {
+        file_path: Some(root_path.clone()),
+        mime_type: None,
+        parents: journal
+            .as_ref()
+            .map(|journal| journal.parents())
+            .unwrap_or_else(|| config.parents.clone()),
+        chunk_size: config.chunk_size.clone(),
+        print_chunk_errors: config.print_chunk_errors,
+        print_chunk_info: config.print_chunk_info,
+        upload_directories: true,
+        print_only_id: false,
+        symlinks: config.symlinks,
+        keep_going: false,
+        failure_report: None,
+        journal: None,
+        resume: None,
+        preserve_created_time: false,
+    };
+
+    let tree = upload::upload_directory(&hub, &upload_config, journal, delegate_config.clone())
+        .await
+        .map_err(Error::InitialUpload)?;
+
+    // The initial upload consumes the journal, it is opened again to record the changes
+    let journal = match &config.journal {
+        Some(path) => Some(UploadJournal::open(path).map_err(Error::Journal)?),
+        None => None,
+    };
+
+    let journal_path = config
+        .journal
+        .as_ref()
+        .and_then(|path| path.canonicalize().ok());
+
+    let mut watch = Watch {
+        hub: &hub,
+        config: &config,
+        delegate_config,
+        root_path: root_path.clone(),
+        journal,
+        journal_path,
+        items: tree_items(&tree),
+        pending: HashMap::new(),
+    };
+
+    println!("Watching '{}' for changes", root_path.display());
+
+    loop {
+        match tokio::time::timeout(TICK, receiver.recv()).await {
+            Ok(Some(Ok(event))) => watch.handle_event(event).await?,
+            Ok(Some(Err(err))) => eprintln!("Warning: Failed to watch for changes: {}", err),
+            Ok(None) => return Err(Error::WatcherStopped),
+            Err(_) => {}
+        }
+
+        watch.apply_ready_changes().await?;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DriveItem {
+    id: String,
+    kind: EntryKind,
+}
+
+// A local path that had events and is waiting for them to settle before it is uploaded
+struct PendingChange {
+    last_event: Instant,
+    open_for_writing: bool,
+}
+
+struct Watch<'a> {
+    hub: &'a Hub,
+    config: &'a Config,
+    delegate_config: UploadDelegateConfig,
+    root_path: PathBuf,
+    journal: Option<UploadJournal>,
+    journal_path: Option<PathBuf>,
+    items: HashMap<PathBuf, DriveItem>,
+    pending: HashMap<PathBuf, PendingChange>,
+}
+
+impl<'a> Watch<'a> {
+    async fn handle_event(&mut self, event: Event) -> Result<(), Error> {
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                self.handle_rename(&event.paths[0], &event.paths[1]).await?;
+            }
+
+            EventKind::Create(CreateKind::Folder)
+            | EventKind::Modify(ModifyKind::Name(_))
+            | EventKind::Remove(_) => {
+                for path in &event.paths {
+                    self.mark_changed(path, false);
+                }
+            }
+
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any) => {
+                for path in &event.paths {
+                    self.mark_changed(path, WAITS_FOR_CLOSE);
+                }
+            }
+
+            EventKind::Access(AccessKind::Close(AccessMode::Write)) => {
+                for path in &event.paths {
+                    if let Some(change) = self.pending.get_mut(path) {
+                        change.last_event = Instant::now();
+                        change.open_for_writing = false;
+                    }
+                }
+            }
+
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn mark_changed(&mut self, path: &PathBuf, open_for_writing: bool) {
+        // Changes to the journal would otherwise be uploaded forever
+        if self.journal_path.as_ref() == Some(path) {
+            return;
+        }
+
+        self.pending.insert(
+            path.clone(),
+            PendingChange {
+                last_event: Instant::now(),
+                open_for_writing,
+            },
+        );
+    }
+
+    async fn apply_ready_changes(&mut self) -> Result<(), Error> {
+        let now = Instant::now();
+
+        let mut ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, change)| {
+                !change.open_for_writing
+                    && now.duration_since(change.last_event) >= self.config.debounce
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        // Parent directories are handled before their children
+        ready.sort_by_key(|path| path.components().count());
+
+        for path in ready {
+            self.pending.remove(&path);
+
+            match self.apply_change(&path).await {
+                Ok(()) => {}
+                Err(Error::Journal(err)) => return Err(Error::Journal(err)),
+                Err(err) => eprintln!(
+                    "Warning: Failed '{}': {}",
+                    self.relative_path(&path).display(),
+                    err
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn apply_change(&mut self, path: &PathBuf) -> Result<(), Error> {
+        // Removed files are kept on drive, they are only forgotten locally
+        if fs::symlink_metadata(path).is_err() {
+            self.forget(path);
+            return Ok(());
+        }
+
+        if self.is_ignored(path) {
+            return Ok(());
+        }
+
+        if path.is_dir() {
+            self.upload_new_items(path).await
+        } else {
+            self.upload_or_update_file(path).await
+        }
+    }
+
+    // Renames and moves the drive file instead of uploading it again
+    async fn handle_rename(&mut self, from: &PathBuf, to: &PathBuf) -> Result<(), Error> {
+        self.pending.remove(from);
+        self.pending.remove(to);
+
+        let item = self
+            .items
+            .get(from)
+            .filter(|_| !self.items.contains_key(to))
+            .cloned();
+
+        let item = match item {
+            Some(item) => item,
+
+            None => {
+                // The file was not uploaded yet or replaced an uploaded file
+                self.forget(from);
+                self.mark_changed(to, false);
+                return Ok(());
+            }
+        };
+
+        match self.move_item(&item, from, to).await {
+            Ok(()) => self.move_items(from, to),
+
+            Err(Error::Journal(err)) => Err(Error::Journal(err)),
+
+            Err(err) => {
+                eprintln!(
+                    "Warning: Failed to move '{}' on drive, it will be uploaded again: {}",
+                    self.relative_path(from).display(),
+                    err
+                );
+                self.forget(from);
+                self.mark_changed(to, false);
+                Ok(())
+            }
+        }
+    }
+
+    async fn move_item(
+        &mut self,
+        item: &DriveItem,
+        from: &PathBuf,
+        to: &PathBuf,
+    ) -> Result<(), Error> {
+        if from.file_name() != to.file_name() {
+            let rename_config = rename::Config {
+                file_id: item.id.clone(),
+                name: file_name(to)?,
+            };
+
+            rename::rename_file(self.hub, self.delegate_config.clone(), &rename_config)
+                .await
+                .map_err(Error::Rename)?;
+        }
+
+        let old_parent = from.parent().map(|path| path.to_path_buf());
+        let new_parent = to.parent().map(|path| path.to_path_buf());
+
+        if old_parent != new_parent {
+            let old_parent = old_parent.ok_or(Error::InvalidPath(from.clone()))?;
+            let new_parent = new_parent.ok_or(Error::InvalidPath(to.clone()))?;
+
+            let old_parent_id = self
+                .items
+                .get(&old_parent)
+                .map(|item| item.id.clone())
+                .ok_or(Error::UnknownParent(old_parent))?;
+
+            let new_parent_id = self.ensure_folder(&new_parent).await?;
+
+            println!(
+                "Moving '{}' to '{}'",
+                self.relative_path(from).display(),
+                self.relative_path(to).display()
+            );
+
+            let change_parent_config = mv::ChangeParentConfig {
+                file_id: item.id.clone(),
+                old_parent_id,
+                new_parent_id,
+            };
+
+            mv::change_parent(
+                self.hub,
+                self.delegate_config.clone(),
+                &change_parent_config,
+            )
+            .await
+            .map_err(Error::Move)?;
+        }
+
+        Ok(())
+    }
+
+    // Uploads a new directory and the files in it that were not uploaded yet.
+    // Files created right after the directory may not have had events of their own
+    async fn upload_new_items(&mut self, dir: &PathBuf) -> Result<(), Error> {
+        self.ensure_folder(dir).await?;
+
+        let mut dirs = vec![dir.clone()];
+
+        while let Some(dir) = dirs.pop() {
+            let entries = fs::read_dir(&dir).map_err(|err| Error::ReadDir(dir.clone(), err))?;
+
+            for entry in entries {
+                let path = entry
+                    .map_err(|err| Error::ReadDir(dir.clone(), err))?
+                    .path();
+
+                if self.is_ignored(&path) || self.pending.contains_key(&path) {
+                    continue;
+                }
+
+                if path.is_dir() {
+                    self.ensure_folder(&path).await?;
+                    dirs.push(path);
+                } else if !self.items.contains_key(&path) {
+                    self.upload_or_update_file(&path).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn upload_or_update_file(&mut self, path: &PathBuf) -> Result<(), Error> {
+        let os_file = fs::File::open(path).map_err(|err| Error::OpenFile(path.clone(), err))?;
+
+        match self.items.get(path).cloned() {
+            Some(item) => {
+                let file_info = FileInfo::from_file(
+                    &os_file,
+                    &file_info::Config {
+                        file_path: path.clone(),
+                        mime_type: None,
+                        parents: None,
+                    },
+                )
+                .map_err(Error::FileInfo)?;
+
+                println!(
+                    "Updating file '{}' with id: {}",
+                    self.relative_path(path).display(),
+                    item.id
+                );
+
+                files::update::update_file(
+                    self.hub,
+                    os_file,
+                    &item.id,
+                    file_info,
+                    self.delegate_config.clone(),
+                )
+                .await
+                .map_err(Error::Update)?;
+
+                self.record(path, item)
+            }
+
+            None => {
+                let parent = path
+                    .parent()
+                    .map(|parent| parent.to_path_buf())
+                    .ok_or(Error::InvalidPath(path.clone()))?;
+
+                let parent_id = self.ensure_folder(&parent).await?;
+
+                let file_info = FileInfo::from_file(
+                    &os_file,
+                    &file_info::Config {
+                        file_path: path.clone(),
+                        mime_type: None,
+                        parents: Some(vec![parent_id]),
+                    },
+                )
+                .map_err(Error::FileInfo)?;
+
+                println!("Uploading file '{}'", self.relative_path(path).display());
+
+                let file = upload::upload_file(
+                    self.hub,
+                    os_file,
+                    None,
+                    file_info,
+                    self.delegate_config.clone(),
+                )
+                .await
+                .map_err(Error::Upload)?;
+
+                let item = DriveItem {
+                    id: file.id.ok_or(Error::DriveItemMissingId)?,
+                    kind: EntryKind::File,
+                };
+
+                self.record(path, item)
+            }
+        }
+    }
+
+    // Creates the directory and any missing parents on drive, returns the drive id of the directory
+    async fn ensure_folder(&mut self, path: &PathBuf) -> Result<String, Error> {
+        if !path.starts_with(&self.root_path) {
+            return Err(Error::InvalidPath(path.clone()));
+        }
+
+        let mut missing = vec![];
+        let mut current = path.clone();
+
+        let mut parent_id = loop {
+            if let Some(item) = self.items.get(&current) {
+                break item.id.clone();
+            }
+
+            missing.push(current.clone());
+
+            current = current
+                .parent()
+                .map(|parent| parent.to_path_buf())
+                .ok_or(Error::InvalidPath(path.clone()))?;
+        };
+
+        for folder_path in missing.into_iter().rev() {
+            println!(
+                "Creating directory '{}'",
+                self.relative_path(&folder_path).display()
+            );
+
+            let mkdir_config = mkdir::Config {
+                id: None,
+                name: file_name(&folder_path)?,
+                parents: Some(vec![parent_id]),
+                print_only_id: false,
+            };
+
+            let folder =
+                mkdir::create_directory(self.hub, &mkdir_config, self.delegate_config.clone())
+                    .await
+                    .map_err(Error::Mkdir)?;
+
+            parent_id = folder.id.ok_or(Error::DriveItemMissingId)?;
+
+            let item = DriveItem {
+                id: parent_id.clone(),
+                kind: EntryKind::Folder,
+            };
+
+            self.record(&folder_path, item)?;
+        }
+
+        Ok(parent_id)
+    }
+
+    // Applies the rules of recursive uploads: special files are skipped and symlinks are only
+    // followed if they are not broken and do not create a loop
+    fn is_ignored(&self, path: &PathBuf) -> bool {
+        let is_symlink = fs::symlink_metadata(path)
+            .map(|metadata| metadata.file_type().is_symlink())
+            .unwrap_or(false);
+
+        if is_symlink {
+            if self.config.symlinks != SymlinkAction::Follow {
+                eprintln!(
+                    "Warning: Skipping symlink '{}', only --symlinks follow is supported for changes",
+                    path.display()
+                );
+                return true;
+            }
+
+            if !path.exists() {
+                eprintln!("Warning: Skipping broken symlink '{}'", path.display());
+                return true;
+            }
+
+            let creates_loop = path.is_dir()
+                && path
+                    .canonicalize()
+                    .ok()
+                    .zip(path.parent().and_then(|parent| parent.canonicalize().ok()))
+                    .map(|(target, parent)| parent.starts_with(target))
+                    .unwrap_or(false);
+
+            if creates_loop {
+                eprintln!(
+                    "Warning: Skipping symlink '{}', following it would create a loop",
+                    path.display()
+                );
+                return true;
+            }
+        }
+
+        if !path.is_dir() && !path.is_file() {
+            // Sockets, fifos, devices, etc
+            eprintln!("Warning: Skipping special file '{}'", path.display());
+            return true;
+        }
+
+        false
+    }
+
+    fn record(&mut self, path: &PathBuf, item: DriveItem) -> Result<(), Error> {
+        if let Some(journal) = &mut self.journal {
+            journal
+                .record(journal_entry(path, &item))
+                .map_err(Error::Journal)?;
+        }
+
+        self.items.insert(path.clone(), item);
+        Ok(())
+    }
+
+    // Moves the drive ids of the path and everything below it to the new path
+    fn move_items(&mut self, from: &PathBuf, to: &PathBuf) -> Result<(), Error> {
+        let moved: Vec<(PathBuf, DriveItem)> = self
+            .items
+            .iter()
+            .filter(|(path, _)| path.starts_with(from))
+            .map(|(path, item)| (path.clone(), item.clone()))
+            .collect();
+
+        for (path, item) in moved {
+            self.items.remove(&path);
+
+            let relative = path.strip_prefix(from).unwrap_or(&path);
+            let new_path = if relative.as_os_str().is_empty() {
+                to.clone()
+            } else {
+                to.join(relative)
+            };
+
+            self.record(&new_path, item)?;
+        }
+
+        Ok(())
+    }
+
+    fn forget(&mut self, path: &PathBuf) {
+        self.items
+            .retain(|item_path, _| !item_path.starts_with(path));
+    }
+
+    fn relative_path(&self, path: &PathBuf) -> PathBuf {
+        let base = self.root_path.parent().unwrap_or(&self.root_path);
+        path.strip_prefix(base).unwrap_or(path).to_path_buf()
+    }
+}
+
+fn open_journal(config: &Config, root_path: &PathBuf) -> Result<Option<UploadJournal>, Error> {
+    let path = match &config.journal {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    if !path.exists() {
+        let journal =
+            UploadJournal::create(path, root_path, &config.parents).map_err(Error::Journal)?;
+        return Ok(Some(journal));
+    }
+
+    let journal = UploadJournal::open(path).map_err(Error::Journal)?;
+
+    if &journal.root_path() != root_path {
+        return Err(Error::JournalRootMismatch(
+            path.clone(),
+            journal.root_path(),
+        ));
+    }
+
+    Ok(Some(journal))
+}
+
+fn tree_items(tree: &FileTree) -> HashMap<PathBuf, DriveItem> {
+    let mut items = HashMap::new();
+
+    for folder in tree.folders() {
+        let item = DriveItem {
+            id: folder.drive_id.clone(),
+            kind: EntryKind::Folder,
+        };
+        items.insert(folder.path.clone(), item);
+
+        for file in folder.files() {
+            let item = DriveItem {
+                id: file.drive_id.clone(),
+                kind: EntryKind::File,
+            };
+            items.insert(file.path.clone(), item);
+        }
+    }
+
+    items
+}
+
+fn journal_entry(path: &PathBuf, item: &DriveItem) -> JournalEntry {
+    let metadata = match item.kind {
+        EntryKind::Folder | EntryKind::Shortcut => None,
+        EntryKind::File => fs::metadata(path).ok(),
+    };
+
+    JournalEntry {
+        path: path.clone(),
+        kind: item.kind,
+        drive_id: item.id.clone(),
+        size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+        modified_time: metadata
+            .as_ref()
+            .and_then(file_info::modified_time)
+            .map(|time| time.to_rfc3339()),
+    }
+}
+
+fn file_name(path: &PathBuf) -> Result<String, Error> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or(Error::InvalidPath(path.clone()))
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    CanonicalizePath(PathBuf, io::Error),
+    NotDirectory(PathBuf),
+    Watch(notify::Error),
+    WatcherStopped,
+    InitialUpload(upload::Error),
+    Journal(upload_journal::Error),
+    JournalRootMismatch(PathBuf, PathBuf),
+    OpenFile(PathBuf, io::Error),
+    ReadDir(PathBuf, io::Error),
+    FileInfo(file_info::Error),
+    Upload(google_drive3::Error),
+    Update(google_drive3::Error),
+    Mkdir(google_drive3::Error),
+    Rename(rename::Error),
+    Move(google_drive3::Error),
+    DriveItemMissingId,
+    InvalidPath(PathBuf),
+    UnknownParent(PathBuf),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::InitialUpload(err) => err.exit_code(),
+            _ => 1,
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::CanonicalizePath(path, err) => write!(
+                f,
+                "Failed to get canonical path of {}: {}",
+                path.display(),
+                err
+            ),
+            Error::NotDirectory(path) => write!(f, "'{}' is not a directory", path.display()),
+            Error::Watch(err) => write!(f, "Failed to watch directory: {}", err),
+            Error::WatcherStopped => write!(f, "The filesystem watcher stopped unexpectedly"),
+            Error::InitialUpload(err) => write!(f, "{}", err),
+            Error::Journal(err) => write!(f, "{}", err),
+            Error::JournalRootMismatch(path, root_path) => write!(
+                f,
+                "Journal '{}' belongs to the directory '{}'",
+                path.display(),
+                root_path.display()
+            ),
+            Error::OpenFile(path, err) => {
+                write!(f, "Failed to open file '{}': {}", path.display(), err)
+            }
+            Error::ReadDir(path, err) => {
+                write!(f, "Failed to read directory '{}': {}", path.display(), err)
+            }
+            Error::FileInfo(err) => write!(f, "{}", err),
+            Error::Upload(err) => write!(f, "Failed to upload file: {}", err),
+            Error::Update(err) => write!(f, "Failed to update file: {}", err),
+            Error::Mkdir(err) => write!(f, "Failed to create directory: {}", err),
+            Error::Rename(err) => write!(f, "{}", err),
+            Error::Move(err) => write!(f, "Failed to move file: {}", err),
+            Error::DriveItemMissingId => write!(f, "File created on drive does not have an id"),
+            Error::InvalidPath(path) => write!(f, "Invalid path: {}", path.display()),
+            Error::UnknownParent(path) => write!(
+                f,
+                "The directory '{}' has not been uploaded",
+                path.display()
+            ),
+        }
+    }
+}
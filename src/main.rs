@@ -1,6 +1,7 @@
 pub mod about;
 pub mod account;
 pub mod app_config;
+pub mod changes;
 pub mod common;
 pub mod drives;
 pub mod files;
@@ -9,13 +10,22 @@ pub mod permissions;
 pub mod version;
 
 use clap::{Parser, Subcommand};
+use common::batch;
 use common::delegate::ChunkSize;
+use common::existing_file::ExistingFileAction;
+use common::file_query;
+use common::file_query::FileQuery;
+use common::file_query::FileTypeFilter;
+use common::file_query::QueryDateTime;
+use common::file_tree::SymlinkAction;
 use common::permission;
+use files::list::ListColumn;
 use files::list::ListQuery;
 use files::list::ListSortOrder;
 use mime::Mime;
 use std::error::Error;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None, disable_version_flag = true)]
@@ -35,6 +45,12 @@ enum Command {
         command: AccountCommand,
     },
 
+    /// Commands for following changes to files
+    Changes {
+        #[command(subcommand)]
+        command: ChangesCommand,
+    },
+
     /// Commands for managing drives
     Drives {
         #[command(subcommand)]
@@ -93,10 +109,70 @@ enum AccountCommand {
     },
 }
 
+#[derive(Subcommand)]
+enum ChangesCommand {
+    /// Print a token for the current state, use it with `changes list --since` to get the changes made after this point
+    StartToken {
+        /// Get a token for the changes of a shared drive
+        #[arg(long, value_name = "DRIVE_ID")]
+        drive: Option<String>,
+    },
+
+    /// List changes since a token. Files that have not been modified since they were created are reported as added
+    List {
+        /// Token from `changes start-token` or from an earlier list
+        #[arg(long, value_name = "TOKEN")]
+        since: String,
+
+        /// List changes of a shared drive, the token must be for the same drive
+        #[arg(long, value_name = "DRIVE_ID")]
+        drive: Option<String>,
+
+        /// Only list changes to files directly in this folder
+        #[arg(long, value_name = "FOLDER_ID")]
+        folder: Option<String>,
+
+        /// Don't print header
+        #[arg(long)]
+        skip_header: bool,
+
+        /// Field separator
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
+    },
+
+    /// Poll for changes and print each event as a json line, or run a hook command for it. The position is saved in the account directory, a restarted watch continues where it stopped. With --folder, files that are new to the folder are reported as added
+    Watch {
+        /// Watch changes of a shared drive
+        #[arg(long, value_name = "DRIVE_ID")]
+        drive: Option<String>,
+
+        /// Only watch changes to files directly in this folder
+        #[arg(long, value_name = "FOLDER_ID")]
+        folder: Option<String>,
+
+        /// Seconds between polls
+        #[arg(long, value_name = "SECONDS", default_value_t = 30)]
+        interval: u64,
+
+        /// Shell command to run for each event. The event is passed in the environment variables GDRIVE_EVENT, GDRIVE_FILE_ID, GDRIVE_FILE_NAME, GDRIVE_MIME_TYPE, GDRIVE_PARENTS, GDRIVE_DRIVE_ID and GDRIVE_EVENT_JSON
+        #[arg(long, value_name = "COMMAND")]
+        hook: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 enum DriveCommand {
     /// List drives
     List {
+        /// Query. See https://developers.google.com/drive/api/guides/search-shareddrives
+        #[arg(long)]
+        query: Option<String>,
+
+        /// Issue the request as a domain administrator, lists all shared drives of the domain
+        #[arg(long)]
+        use_domain_admin_access: bool,
+
         /// Don't print header
         #[arg(long)]
         skip_header: bool,
@@ -105,6 +181,65 @@ enum DriveCommand {
         #[arg(long, default_value_t = String::from("\t"))]
         field_separator: String,
     },
+
+    /// Print drive info
+    Info {
+        /// Drive id
+        drive_id: String,
+
+        /// Issue the request as a domain administrator
+        #[arg(long)]
+        use_domain_admin_access: bool,
+    },
+
+    /// Create shared drive
+    Create {
+        /// Name
+        name: String,
+
+        /// Request id used to make the request idempotent. A random id is generated if not given
+        #[arg(long)]
+        request_id: Option<String>,
+
+        /// Print only id of drive
+        #[arg(long, default_value_t = false)]
+        print_only_id: bool,
+    },
+
+    /// Rename shared drive
+    Rename {
+        /// Drive id
+        drive_id: String,
+
+        /// New name
+        name: String,
+    },
+
+    /// Hide shared drive from the default view
+    Hide {
+        /// Drive id
+        drive_id: String,
+    },
+
+    /// Restore shared drive to the default view
+    Unhide {
+        /// Drive id
+        drive_id: String,
+    },
+
+    /// Delete shared drive
+    Delete {
+        /// Drive id
+        drive_id: String,
+
+        /// Delete all items on the drive before deleting the drive
+        #[arg(long)]
+        recursive: bool,
+
+        /// Issue the request as a domain administrator
+        #[arg(long)]
+        use_domain_admin_access: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -125,9 +260,9 @@ enum FileCommand {
         #[arg(long, default_value_t = 30)]
         max: usize,
 
-        /// Query. See https://developers.google.com/drive/search-parameters
-        #[arg(long, default_value_t = ListQuery::default())]
-        query: ListQuery,
+        /// Query. See https://developers.google.com/drive/search-parameters. Defaults to files in the root folder that are not trashed
+        #[arg(long)]
+        query: Option<ListQuery>,
 
         /// Order by. See https://developers.google.com/drive/api/v3/reference/files/list
         #[arg(long, default_value_t = ListSortOrder::default())]
@@ -138,9 +273,61 @@ enum FileCommand {
         parent: Option<String>,
 
         /// List files on a shared drive
-        #[arg(long, value_name = "DRIVE_ID")]
+        #[arg(long, value_name = "DRIVE_ID", conflicts_with = "parent")]
         drive: Option<String>,
 
+        /// Only list files with this exact name. The filters search all folders unless combined with --parent or --drive
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Only list files with a name containing this text
+        #[arg(long)]
+        name_contains: Option<String>,
+
+        /// Only list files with this mime type
+        #[arg(long)]
+        mime: Option<String>,
+
+        /// Only list files of this type
+        #[arg(long = "type", value_name = "folder|document|regular|shortcut")]
+        file_type: Option<FileTypeFilter>,
+
+        /// Only list files modified after this date (2023-01-31) or time (2023-01-31T12:00:00Z)
+        #[arg(long, value_name = "TIME")]
+        modified_after: Option<QueryDateTime>,
+
+        /// Only list files modified before this date (2023-01-31) or time (2023-01-31T12:00:00Z)
+        #[arg(long, value_name = "TIME")]
+        modified_before: Option<QueryDateTime>,
+
+        /// Only list files owned by this user
+        #[arg(long, value_name = "EMAIL")]
+        owner: Option<String>,
+
+        /// Only list files shared with me
+        #[arg(long)]
+        shared_with_me: bool,
+
+        /// Only list starred files
+        #[arg(long)]
+        starred: bool,
+
+        /// List trashed files instead of files that are not trashed
+        #[arg(long)]
+        trashed: bool,
+
+        /// Only list files where the name, description or content contains this text
+        #[arg(long, value_name = "TEXT")]
+        full_text: Option<String>,
+
+        /// List all files below the parent folder or drive, with their path relative to it
+        #[arg(long, conflicts_with_all = ["query", "name", "name_contains", "mime", "file_type", "modified_after", "modified_before", "owner", "shared_with_me", "starred", "trashed", "full_text", "columns"])]
+        recursive: bool,
+
+        /// Max depth of the recursive listing, 1 only lists direct children
+        #[arg(long, requires = "recursive")]
+        max_depth: Option<usize>,
+
         /// Don't print header
         #[arg(long)]
         skip_header: bool,
@@ -149,6 +336,43 @@ enum FileCommand {
         #[arg(long)]
         full_name: bool,
 
+        /// Columns to print, separated by comma. Valid columns are: id, name, type, size, created, modified, md5, owners, parents, mime, shared, starred, web-view-link, path
+        #[arg(long, value_delimiter = ',', default_values_t = ListColumn::default_columns())]
+        columns: Vec<ListColumn>,
+
+        /// Field separator
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
+    },
+
+    /// Print the folder hierarchy as a tree
+    Tree {
+        /// Folder or drive id, defaults to the root folder
+        #[arg(default_value_t = String::from("root"))]
+        folder_id: String,
+
+        /// Max depth of the tree, 1 only prints direct children
+        #[arg(long)]
+        max_depth: Option<usize>,
+    },
+
+    /// Print disk usage of a folder, grouped by subfolder, mime type or owner
+    Du {
+        /// Folder or drive id
+        folder_id: String,
+
+        /// How to group the usage
+        #[arg(long, value_name = "folder|mime|owner", default_value_t = files::du::GroupBy::default())]
+        by: files::du::GroupBy,
+
+        /// Display size in bytes
+        #[arg(long, default_value_t = false)]
+        size_in_bytes: bool,
+
+        /// Don't print header
+        #[arg(long)]
+        skip_header: bool,
+
         /// Field separator
         #[arg(long, default_value_t = String::from("\t"))]
         field_separator: String,
@@ -159,10 +383,14 @@ enum FileCommand {
         /// File id
         file_id: String,
 
-        /// Overwrite existing files and folders
-        #[arg(long)]
+        /// Overwrite existing files and folders. Same as --on-conflict overwrite
+        #[arg(long, conflicts_with = "on_conflict")]
         overwrite: bool,
 
+        /// What to do when a file already exists locally. 'rename' writes to 'name (1).ext', 'if-newer' only downloads if the file on drive was modified after the local file
+        #[arg(long, value_name = "abort|overwrite|skip|rename|if-newer", default_value_t = ExistingFileAction::default())]
+        on_conflict: ExistingFileAction,
+
         /// Follow shortcut and download target file (does not work with recursive download)
         #[arg(long)]
         follow_shortcuts: bool,
@@ -178,6 +406,18 @@ enum FileCommand {
         /// Write file to stdout
         #[arg(long)]
         stdout: bool,
+
+        /// Write a json manifest mapping drive ids and names to local paths. Names that are not valid on the local filesystem are escaped, and duplicate names get the file id appended
+        #[arg(long, value_name = "PATH")]
+        manifest: Option<PathBuf>,
+
+        /// Continue downloading the remaining files when a file fails. A summary of the failures is printed at the end and the exit code is 2
+        #[arg(long)]
+        keep_going: bool,
+
+        /// Write failures to a json file (requires --keep-going)
+        #[arg(long, value_name = "PATH", requires = "keep_going")]
+        failure_report: Option<PathBuf>,
     },
 
     /// Upload file
@@ -197,6 +437,10 @@ enum FileCommand {
         #[arg(long)]
         recursive: bool,
 
+        /// How to handle symlinks when uploading directories. 'shortcut' creates a shortcut on drive when the target is inside the uploaded directory
+        #[arg(long, value_name = "follow|skip|shortcut|error", default_value_t = SymlinkAction::default())]
+        symlinks: SymlinkAction,
+
         /// Set chunk size in MB, must be a power of two.
         #[arg(long, value_name = "1|2|4|8|16|32|64|128|256|512|1024|4096|8192", default_value_t = ChunkSize::default())]
         chunk_size: ChunkSize,
@@ -212,6 +456,73 @@ enum FileCommand {
         /// Print only id of file/folder
         #[arg(long, default_value_t = false)]
         print_only_id: bool,
+
+        /// Continue uploading the remaining files when a file fails. A summary of the failures is printed at the end and the exit code is 2
+        #[arg(long)]
+        keep_going: bool,
+
+        /// Write failures to a json file (requires --keep-going)
+        #[arg(long, value_name = "PATH", requires = "keep_going")]
+        failure_report: Option<PathBuf>,
+
+        /// Write a journal of the planned and completed uploads when uploading directories. The journal can be used with --resume to continue an interrupted upload
+        #[arg(long, value_name = "PATH")]
+        journal: Option<PathBuf>,
+
+        /// Resume an interrupted directory upload from a journal created with --journal. Already uploaded folders and files are skipped, files that changed since are updated
+        #[arg(long, value_name = "JOURNAL_PATH", conflicts_with_all = ["file_path", "parent", "journal"])]
+        resume: Option<PathBuf>,
+
+        /// Keep the local creation time of the files instead of the upload time. Not all platforms and filesystems have a creation time
+        #[arg(long)]
+        preserve_created_time: bool,
+    },
+
+    /// Download a directory and keep it in sync with drive. Changes on drive are polled and applied locally: new and modified files are downloaded, renamed and moved files are moved, trashed files and files moved out of the directory are removed. The position is saved in the account directory, a restarted watch only applies the changes since
+    WatchDownload {
+        /// Directory id
+        folder_id: String,
+
+        /// Local directory to download the directory to
+        local_dir: PathBuf,
+
+        /// Seconds between polls
+        #[arg(long, value_name = "SECONDS", default_value_t = 30)]
+        interval: u64,
+    },
+
+    /// Upload a directory and keep watching it, new files are uploaded and modified files are updated. Renames and moves are applied to the existing files on drive. Removed files are kept on drive
+    WatchUpload {
+        /// Path of directory to watch
+        path: PathBuf,
+
+        /// Upload to an existing directory
+        #[arg(long, value_name = "DIRECTORY_ID")]
+        parent: Option<Vec<String>>,
+
+        /// Seconds to wait after the last change of a file before it is uploaded
+        #[arg(long, value_name = "SECONDS", default_value_t = 2)]
+        debounce: u64,
+
+        /// How to handle symlinks. Only 'follow' applies to symlinks created while watching, others are skipped
+        #[arg(long, value_name = "follow|skip|shortcut|error", default_value_t = SymlinkAction::default())]
+        symlinks: SymlinkAction,
+
+        /// Keep the drive ids of the uploaded files in a journal. When the journal exists the directory is not uploaded again on start, only changed and new files are
+        #[arg(long, value_name = "PATH")]
+        journal: Option<PathBuf>,
+
+        /// Set chunk size in MB, must be a power of two.
+        #[arg(long, value_name = "1|2|4|8|16|32|64|128|256|512|1024|4096|8192", default_value_t = ChunkSize::default())]
+        chunk_size: ChunkSize,
+
+        /// Print errors occuring during chunk upload
+        #[arg(long, value_name = "", default_value_t = false)]
+        print_chunk_errors: bool,
+
+        /// Print details about each chunk
+        #[arg(long, value_name = "", default_value_t = false)]
+        print_chunk_info: bool,
     },
 
     /// Update file. This will create a new version of the file. The older versions will typically be kept for 30 days.
@@ -241,12 +552,21 @@ enum FileCommand {
 
     /// Delete file
     Delete {
-        /// File id
-        file_id: String,
+        /// File ids
+        #[arg(required_unless_present = "ids_from")]
+        file_ids: Vec<String>,
 
         /// Delete directory and all it's content
         #[arg(long)]
         recursive: bool,
+
+        /// Read additional file ids from a file, one id per line. Use '-' to read from stdin
+        #[arg(long, value_name = "FILE")]
+        ids_from: Option<PathBuf>,
+
+        /// Max number of batch requests in flight when operating on multiple files, each batch holds up to 100 files
+        #[arg(long, default_value_t = batch::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
     },
 
     /// Create directory
@@ -263,22 +583,56 @@ enum FileCommand {
         print_only_id: bool,
     },
 
+    /// Create a shortcut to a file or directory
+    Shortcut {
+        /// Id of the file or directory the shortcut points to
+        target_id: String,
+
+        /// Name of the shortcut. Defaults to the name of the target
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Create in an existing directory
+        #[arg(long, value_name = "DIRECTORY_ID")]
+        parent: Option<Vec<String>>,
+
+        /// Print only id of shortcut
+        #[arg(long, default_value_t = false)]
+        print_only_id: bool,
+    },
+
     /// Rename file/directory
     Rename {
         /// Id of file or directory
-        file_id: String,
+        #[arg(required_unless_present = "ids_from")]
+        file_id: Option<String>,
 
         /// New name
-        name: String,
+        #[arg(required_unless_present = "ids_from")]
+        name: Option<String>,
+
+        /// Rename files listed in a file, one '<file id> <new name>' per line. Use '-' to read from stdin
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["file_id", "name"])]
+        ids_from: Option<PathBuf>,
+
+        /// Max number of batch requests in flight when operating on multiple files, each batch holds up to 100 files
+        #[arg(long, default_value_t = batch::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
     },
 
     /// Move file/directory
     Move {
-        /// Id of file or directory to move
-        file_id: String,
+        /// Ids of files or directories to move, followed by the id of the folder to move to
+        #[arg(required = true, value_name = "ID")]
+        ids: Vec<String>,
 
-        /// Id of folder to move to
-        folder_id: String,
+        /// Read additional file ids from a file, one id per line. Use '-' to read from stdin
+        #[arg(long, value_name = "FILE")]
+        ids_from: Option<PathBuf>,
+
+        /// Max number of batch requests in flight when operating on multiple files, each batch holds up to 100 files
+        #[arg(long, default_value_t = batch::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
     },
 
     /// Copy file
@@ -313,9 +667,13 @@ enum FileCommand {
         /// File path to export to. The file extension will determine the export format
         file_path: PathBuf,
 
-        /// Overwrite existing files
-        #[arg(long)]
+        /// Overwrite existing files. Same as --on-conflict overwrite
+        #[arg(long, conflicts_with = "on_conflict")]
         overwrite: bool,
+
+        /// What to do when the file already exists locally. 'rename' writes to 'name (1).ext', 'if-newer' only exports if the file on drive was modified after the local file
+        #[arg(long, value_name = "abort|overwrite|skip|rename|if-newer", default_value_t = ExistingFileAction::default())]
+        on_conflict: ExistingFileAction,
     },
 }
 
@@ -323,8 +681,9 @@ enum FileCommand {
 enum PermissionCommand {
     /// Grant permission to file
     Share {
-        /// File id
-        file_id: String,
+        /// File ids
+        #[arg(required_unless_present = "ids_from")]
+        file_ids: Vec<String>,
 
         /// The role granted by this permission. Allowed values are: owner, organizer, fileOrganizer, writer, commenter, reader
         #[arg(long, default_value_t = permission::Role::default())]
@@ -345,6 +704,64 @@ enum PermissionCommand {
         /// Whether the permission allows the file to be discovered through search. This is only applicable for permissions of type domain or anyone
         #[arg(long)]
         discoverable: bool,
+
+        /// Send a notification email to the grantee. Only applicable for user and group type, drive notifies by default except for recursive shares
+        #[arg(long, conflicts_with = "no_notify")]
+        notify: bool,
+
+        /// Don't send a notification email to the grantee. Not allowed when transferring ownership
+        #[arg(long)]
+        no_notify: bool,
+
+        /// Custom message to include in the notification email. Requires --notify for recursive shares
+        #[arg(long, conflicts_with = "no_notify")]
+        message: Option<String>,
+
+        /// When the permission expires, as a RFC 3339 timestamp or a duration from now like 12h, 7d or 2w. Only applicable for reader, commenter and writer permissions of users and groups
+        #[arg(long, value_name = "RFC3339|DURATION")]
+        expires: Option<permission::Expiration>,
+
+        /// Apply to everything below the given folders, including documents and shortcuts
+        #[arg(long)]
+        recursive: bool,
+
+        /// Print the items that would be shared without sharing them
+        #[arg(long, requires = "recursive")]
+        dry_run: bool,
+
+        /// Read additional file ids from a file, one id per line. Use '-' to read from stdin
+        #[arg(long, value_name = "FILE")]
+        ids_from: Option<PathBuf>,
+
+        /// Max number of batch requests in flight when operating on multiple files, each batch holds up to 100 files
+        #[arg(long, default_value_t = batch::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
+    },
+
+    /// Update the role or expiration of an existing permission. Updating a user to owner transfers the ownership of the file, users outside of the organization have to accept it first
+    Update {
+        /// File id
+        file_id: String,
+
+        /// Permission id
+        #[arg(long, value_name = "PERMISSION_ID", required_unless_present = "email")]
+        id: Option<String>,
+
+        /// Email address of the user or group of the permission
+        #[arg(long, conflicts_with = "id")]
+        email: Option<String>,
+
+        /// The new role of the permission. Allowed values are: owner, organizer, fileOrganizer, writer, commenter, reader
+        #[arg(long)]
+        role: permission::Role,
+
+        /// When the permission expires, as a RFC 3339 timestamp or a duration from now like 12h, 7d or 2w
+        #[arg(long, value_name = "RFC3339|DURATION")]
+        expires: Option<permission::Expiration>,
+
+        /// Remove the expiration of the permission
+        #[arg(long, conflicts_with = "expires")]
+        remove_expiration: bool,
     },
 
     /// List permissions for a file
@@ -352,6 +769,10 @@ enum PermissionCommand {
         /// File id
         file_id: String,
 
+        /// Issue the request as a domain administrator, listing the permissions of any file in a shared drive of the domain
+        #[arg(long)]
+        use_domain_admin_access: bool,
+
         /// Don't print header
         #[arg(long)]
         skip_header: bool,
@@ -361,18 +782,108 @@ enum PermissionCommand {
         field_separator: String,
     },
 
+    /// Copy the permissions of a file or folder to other files. The owner and inherited permissions are not copied, grantees that already have access get the role of the source
+    Copy {
+        /// Id of the file to copy the permissions from
+        source_id: String,
+
+        /// Ids of the files to copy the permissions to
+        #[arg(required_unless_present = "ids_from")]
+        file_ids: Vec<String>,
+
+        /// Also revoke the permissions of the destinations that the source doesn't have
+        #[arg(long)]
+        mirror: bool,
+
+        /// Send notification emails to the users and groups that are granted access
+        #[arg(long)]
+        notify: bool,
+
+        /// Read additional file ids from a file, one id per line. Use '-' to read from stdin
+        #[arg(long, value_name = "FILE")]
+        ids_from: Option<PathBuf>,
+
+        /// Max number of batch requests in flight, each batch holds up to 100 files
+        #[arg(long, default_value_t = batch::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
+    },
+
+    /// Report files shared publicly or outside of the domain, and files that can be found by searching. Audits the whole my drive unless a folder or shared drive is given
+    Audit {
+        /// Folder id
+        #[arg(long, conflicts_with = "drive")]
+        folder: Option<String>,
+
+        /// Shared drive id
+        #[arg(long)]
+        drive: Option<String>,
+
+        /// The domain that is considered internal. Defaults to the domain of the current account, for gmail accounts only the account itself is internal
+        #[arg(long)]
+        domain: Option<String>,
+
+        /// Output format
+        #[arg(long, value_name = "table|csv|json", default_value_t = permissions::audit::ReportFormat::default())]
+        format: permissions::audit::ReportFormat,
+
+        /// Don't print header
+        #[arg(long)]
+        skip_header: bool,
+
+        /// Field separator of the table
+        #[arg(long, default_value_t = String::from("\t"))]
+        field_separator: String,
+
+        /// Max number of batch requests in flight, each batch holds up to 100 files
+        #[arg(long, default_value_t = batch::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
+    },
+
     /// Revoke permissions for a file. If no other options are specified, the 'anyone' permission will be revoked
     Revoke {
-        /// File id
-        file_id: String,
+        /// File ids
+        #[arg(required_unless_present = "ids_from")]
+        file_ids: Vec<String>,
 
         /// Revoke all permissions (except owner)
-        #[arg(long)]
+        #[arg(long, conflicts_with_all = ["id", "email", "domain", "role", "type_"])]
         all: bool,
 
         /// Revoke specific permission
-        #[arg(long, value_name = "PERMISSION_ID")]
+        #[arg(long, value_name = "PERMISSION_ID", conflicts_with_all = ["email", "domain", "role", "type_"])]
         id: Option<String>,
+
+        /// Revoke the permissions of the user or group with this email address
+        #[arg(long)]
+        email: Option<String>,
+
+        /// Revoke the permissions of the domain, and of users and groups with an email address in the domain
+        #[arg(long)]
+        domain: Option<String>,
+
+        /// Revoke the permissions with this role. Can be combined with the other filters
+        #[arg(long)]
+        role: Option<permission::Role>,
+
+        /// Revoke the permissions of this type. Can be combined with the other filters
+        #[arg(long)]
+        type_: Option<permission::Type>,
+
+        /// Apply to everything below the given folders, including documents and shortcuts
+        #[arg(long)]
+        recursive: bool,
+
+        /// Print the permissions that would be revoked without revoking them
+        #[arg(long, requires = "recursive")]
+        dry_run: bool,
+
+        /// Read additional file ids from a file, one id per line. Use '-' to read from stdin
+        #[arg(long, value_name = "FILE")]
+        ids_from: Option<PathBuf>,
+
+        /// Max number of batch requests in flight when operating on multiple files, each batch holds up to 100 files
+        #[arg(long, default_value_t = batch::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
     },
 }
 
@@ -432,18 +943,119 @@ async fn main() {
             }
         }
 
+        Command::Changes { command } => {
+            // fmt
+            match command {
+                ChangesCommand::StartToken { drive } => {
+                    // fmt
+                    changes::start_token(changes::start_token::Config { drive_id: drive })
+                        .await
+                        .unwrap_or_else(handle_error)
+                }
+
+                ChangesCommand::List {
+                    since,
+                    drive,
+                    folder,
+                    skip_header,
+                    field_separator,
+                } => changes::list(changes::list::Config {
+                    since,
+                    drive_id: drive,
+                    folder_id: folder,
+                    skip_header,
+                    field_separator,
+                })
+                .await
+                .unwrap_or_else(handle_error),
+
+                ChangesCommand::Watch {
+                    drive,
+                    folder,
+                    interval,
+                    hook,
+                } => changes::watch(changes::watch::Config {
+                    drive_id: drive,
+                    folder_id: folder,
+                    interval: Duration::from_secs(interval),
+                    hook,
+                })
+                .await
+                .unwrap_or_else(handle_error),
+            }
+        }
+
         Command::Drives { command } => {
             // fmt
             match command {
                 DriveCommand::List {
+                    query,
+                    use_domain_admin_access,
                     skip_header,
                     field_separator,
                 } => drives::list(drives::list::Config {
+                    query,
+                    use_domain_admin_access,
                     skip_header,
                     field_separator,
                 })
                 .await
                 .unwrap_or_else(handle_error),
+
+                DriveCommand::Info {
+                    drive_id,
+                    use_domain_admin_access,
+                } => drives::info(drives::info::Config {
+                    drive_id,
+                    use_domain_admin_access,
+                })
+                .await
+                .unwrap_or_else(handle_error),
+
+                DriveCommand::Create {
+                    name,
+                    request_id,
+                    print_only_id,
+                } => drives::create(drives::create::Config {
+                    name,
+                    request_id,
+                    print_only_id,
+                })
+                .await
+                .unwrap_or_else(handle_error),
+
+                DriveCommand::Rename { drive_id, name } => {
+                    // fmt
+                    drives::rename(drives::rename::Config { drive_id, name })
+                        .await
+                        .unwrap_or_else(handle_error)
+                }
+
+                DriveCommand::Hide { drive_id } => {
+                    // fmt
+                    drives::hide(drives::hide::Config { drive_id })
+                        .await
+                        .unwrap_or_else(handle_error)
+                }
+
+                DriveCommand::Unhide { drive_id } => {
+                    // fmt
+                    drives::unhide(drives::unhide::Config { drive_id })
+                        .await
+                        .unwrap_or_else(handle_error)
+                }
+
+                DriveCommand::Delete {
+                    drive_id,
+                    recursive,
+                    use_domain_admin_access,
+                } => drives::delete(drives::delete::Config {
+                    drive_id,
+                    delete_items: recursive,
+                    use_domain_admin_access,
+                })
+                .await
+                .unwrap_or_else(handle_error),
             }
         }
 
@@ -468,23 +1080,123 @@ async fn main() {
                     order_by,
                     parent,
                     drive,
+                    name,
+                    name_contains,
+                    mime,
+                    file_type,
+                    modified_after,
+                    modified_before,
+                    owner,
+                    shared_with_me,
+                    starred,
+                    trashed,
+                    full_text,
+                    recursive,
+                    max_depth,
                     skip_header,
                     full_name,
+                    columns,
                     field_separator,
                 } => {
-                    let parent_query =
-                        parent.map(|folder_id| ListQuery::FilesInFolder { folder_id });
-
-                    let drive_query = drive.map(|drive_id| ListQuery::FilesOnDrive { drive_id });
+                    if recursive {
+                        let folder_id = parent.or(drive).unwrap_or_else(|| String::from("root"));
+
+                        files::list_recursive(files::list_recursive::Config {
+                            folder_id,
+                            max_depth,
+                            skip_header,
+                            field_separator,
+                        })
+                        .await
+                        .unwrap_or_else(handle_error)
+                    } else {
+                        let filter = FileQuery {
+                            parent: parent.clone().or(drive.clone()),
+                            name,
+                            name_contains,
+                            mime_type: mime,
+                            file_type,
+                            modified_after,
+                            modified_before,
+                            owner,
+                            shared_with_me,
+                            starred,
+                            trashed,
+                            full_text,
+                            custom: query
+                                .as_ref()
+                                .map(|q| q.to_string())
+                                .filter(|q| !q.is_empty()),
+                        };
+
+                        let q = if filter.has_filters() {
+                            ListQuery::Filter(filter)
+                        } else {
+                            let query = query.filter(|q| !q.to_string().is_empty());
+
+                            match (parent, drive, query) {
+                                // The query only applies to the files in the folder or on the drive
+                                (Some(parent_id), _, Some(query))
+                                | (None, Some(parent_id), Some(query)) => {
+                                    ListQuery::Custom(format!(
+                                        "{} and ({})",
+                                        file_query::parent_clause(&parent_id),
+                                        query
+                                    ))
+                                }
+
+                                (Some(folder_id), _, None) => {
+                                    ListQuery::FilesInFolder { folder_id }
+                                }
+
+                                (None, Some(drive_id), None) => {
+                                    ListQuery::FilesOnDrive { drive_id }
+                                }
+
+                                (None, None, query) => query.unwrap_or_default(),
+                            }
+                        };
+
+                        files::list(files::list::Config {
+                            query: q,
+                            order_by,
+                            max_files: max,
+                            columns,
+                            skip_header,
+                            truncate_name: !full_name,
+                            field_separator,
+                        })
+                        .await
+                        .unwrap_or_else(handle_error)
+                    }
+                }
 
-                    let q = parent_query.or(drive_query).unwrap_or(query);
+                FileCommand::Tree {
+                    folder_id,
+                    max_depth,
+                } => {
+                    // fmt
+                    files::tree(files::tree::Config {
+                        folder_id,
+                        max_depth,
+                    })
+                    .await
+                    .unwrap_or_else(handle_error)
+                }
 
-                    files::list(files::list::Config {
-                        query: q,
-                        order_by,
-                        max_files: max,
+                FileCommand::Du {
+                    folder_id,
+                    by,
+                    size_in_bytes,
+                    skip_header,
+                    field_separator,
+                } => {
+                    // fmt
+                    files::du(files::du::Config {
+                        folder_id,
+                        group_by: by,
+                        size_in_bytes,
                         skip_header,
-                        truncate_name: !full_name,
                         field_separator,
                     })
                     .await
@@ -494,15 +1206,19 @@ async fn main() {
                 FileCommand::Download {
                     file_id,
                     overwrite,
+                    on_conflict,
                     follow_shortcuts,
                     recursive,
                     destination,
                     stdout,
+                    manifest,
+                    keep_going,
+                    failure_report,
                 } => {
                     let existing_file_action = if overwrite {
-                        files::download::ExistingFileAction::Overwrite
+                        ExistingFileAction::Overwrite
                     } else {
-                        files::download::ExistingFileAction::Abort
+                        on_conflict
                     };
 
                     let dst = if stdout {
@@ -519,9 +1235,12 @@ async fn main() {
                         follow_shortcuts,
                         download_directories: recursive,
                         destination: dst,
+                        manifest_path: manifest,
+                        keep_going,
+                        failure_report,
                     })
                     .await
-                    .unwrap_or_else(handle_error)
+                    .unwrap_or_else(|err| handle_error_with_exit_code(err.exit_code(), err))
                 }
 
                 FileCommand::Upload {
@@ -529,10 +1248,16 @@ async fn main() {
                     mime,
                     parent,
                     recursive,
+                    symlinks,
                     chunk_size,
                     print_chunk_errors,
                     print_chunk_info,
                     print_only_id,
+                    keep_going,
+                    failure_report,
+                    journal,
+                    resume,
+                    preserve_created_time,
                 } => {
                     // fmt
                     files::upload(files::upload::Config {
@@ -544,11 +1269,57 @@ async fn main() {
                         print_chunk_info,
                         upload_directories: recursive,
                         print_only_id,
+                        symlinks,
+                        keep_going,
+                        failure_report,
+                        journal,
+                        resume,
+                        preserve_created_time,
+                    })
+                    .await
+                    .unwrap_or_else(|err| handle_error_with_exit_code(err.exit_code(), err))
+                }
+
+                FileCommand::WatchDownload {
+                    folder_id,
+                    local_dir,
+                    interval,
+                } => {
+                    // fmt
+                    files::watch_download(files::watch_download::Config {
+                        folder_id,
+                        local_dir,
+                        interval: Duration::from_secs(interval),
                     })
                     .await
                     .unwrap_or_else(handle_error)
                 }
 
+                FileCommand::WatchUpload {
+                    path,
+                    parent,
+                    debounce,
+                    symlinks,
+                    journal,
+                    chunk_size,
+                    print_chunk_errors,
+                    print_chunk_info,
+                } => {
+                    // fmt
+                    files::watch_upload(files::watch_upload::Config {
+                        path,
+                        parents: parent,
+                        debounce: Duration::from_secs(debounce),
+                        symlinks,
+                        journal,
+                        chunk_size,
+                        print_chunk_errors,
+                        print_chunk_info,
+                    })
+                    .await
+                    .unwrap_or_else(|err| handle_error_with_exit_code(err.exit_code(), err))
+                }
+
                 FileCommand::Update {
                     file_id,
                     file_path,
@@ -570,15 +1341,36 @@ async fn main() {
                     .unwrap_or_else(handle_error)
                 }
 
-                FileCommand::Delete { file_id, recursive } => {
-                    // fmt
-                    files::delete(files::delete::Config {
-                        file_id,
-                        delete_directories: recursive,
-                    })
-                    .await
-                    .unwrap_or_else(handle_error)
-                }
+                FileCommand::Delete {
+                    file_ids,
+                    recursive,
+                    ids_from,
+                    concurrency,
+                } => match batch::read_ids(&file_ids, &ids_from) {
+                    Err(err) => handle_error(err),
+
+                    Ok(file_ids) if file_ids.is_empty() => handle_error(batch::Error::NoIds),
+
+                    Ok(file_ids) if is_single_id(&file_ids, &ids_from) => {
+                        files::delete(files::delete::Config {
+                            file_id: file_ids[0].clone(),
+                            delete_directories: recursive,
+                        })
+                        .await
+                        .unwrap_or_else(handle_error)
+                    }
+
+                    Ok(file_ids) => {
+                        let config = files::delete::Config {
+                            file_id: String::new(),
+                            delete_directories: recursive,
+                        };
+
+                        files::delete::delete_many(config, file_ids, concurrency)
+                            .await
+                            .unwrap_or_else(|err| handle_error_with_exit_code(err.exit_code(), err))
+                    }
+                },
 
                 FileCommand::Mkdir {
                     name,
@@ -596,21 +1388,82 @@ async fn main() {
                     .unwrap_or_else(handle_error)
                 }
 
-                FileCommand::Rename { file_id, name } => {
+                FileCommand::Shortcut {
+                    target_id,
+                    name,
+                    parent,
+                    print_only_id,
+                } => {
                     // fmt
-                    files::rename(files::rename::Config { file_id, name })
-                        .await
-                        .unwrap_or_else(handle_error)
+                    files::shortcut(files::shortcut::Config {
+                        target_id,
+                        name,
+                        parents: parent,
+                        print_only_id,
+                    })
+                    .await
+                    .unwrap_or_else(handle_error)
                 }
 
-                FileCommand::Move { file_id, folder_id } => {
-                    // fmt
-                    files::mv(files::mv::Config {
-                        file_id,
-                        to_folder_id: folder_id,
+                FileCommand::Rename {
+                    file_id,
+                    name,
+                    ids_from,
+                    concurrency,
+                } => match ids_from {
+                    Some(path) => match batch::read_lines(&path) {
+                        Err(err) => handle_error(err),
+
+                        Ok(lines) => files::rename::rename_many(lines, concurrency)
+                            .await
+                            .unwrap_or_else(|err| {
+                                handle_error_with_exit_code(err.exit_code(), err)
+                            }),
+                    },
+
+                    None => files::rename(files::rename::Config {
+                        file_id: file_id.unwrap_or_default(),
+                        name: name.unwrap_or_default(),
                     })
                     .await
-                    .unwrap_or_else(handle_error)
+                    .unwrap_or_else(handle_error),
+                },
+
+                FileCommand::Move {
+                    mut ids,
+                    ids_from,
+                    concurrency,
+                } => {
+                    // The last id is the folder, clap ensures there is at least one id
+                    let folder_id = ids.pop().unwrap_or_default();
+
+                    match batch::read_ids(&ids, &ids_from) {
+                        Err(err) => handle_error(err),
+
+                        Ok(file_ids) if file_ids.is_empty() => handle_error(batch::Error::NoIds),
+
+                        Ok(file_ids) if is_single_id(&file_ids, &ids_from) => {
+                            files::mv(files::mv::Config {
+                                file_id: file_ids[0].clone(),
+                                to_folder_id: folder_id,
+                            })
+                            .await
+                            .unwrap_or_else(handle_error)
+                        }
+
+                        Ok(file_ids) => {
+                            let config = files::mv::Config {
+                                file_id: String::new(),
+                                to_folder_id: folder_id,
+                            };
+
+                            files::mv::mv_many(config, file_ids, concurrency)
+                                .await
+                                .unwrap_or_else(|err| {
+                                    handle_error_with_exit_code(err.exit_code(), err)
+                                })
+                        }
+                    }
                 }
 
                 FileCommand::Copy { file_id, folder_id } => {
@@ -642,11 +1495,12 @@ async fn main() {
                     file_id,
                     file_path,
                     overwrite,
+                    on_conflict,
                 } => {
                     let existing_file_action = if overwrite {
-                        files::export::ExistingFileAction::Overwrite
+                        ExistingFileAction::Overwrite
                     } else {
-                        files::export::ExistingFileAction::Abort
+                        on_conflict
                     };
 
                     files::export(files::export::Config {
@@ -663,21 +1517,100 @@ async fn main() {
         Command::Permissions { command } => {
             match command {
                 PermissionCommand::Share {
-                    file_id,
+                    file_ids,
                     role,
                     type_,
                     discoverable,
                     email,
                     domain,
+                    notify,
+                    no_notify,
+                    message,
+                    expires,
+                    recursive,
+                    dry_run,
+                    ids_from,
+                    concurrency,
                 } => {
-                    // fmt
-                    permissions::share(permissions::share::Config {
-                        file_id,
+                    let notify = if no_notify {
+                        Some(false)
+                    } else if notify {
+                        Some(true)
+                    } else {
+                        None
+                    };
+
+                    let config = permissions::share::Config {
+                        file_id: String::new(),
                         role,
                         type_,
                         discoverable,
                         email,
                         domain,
+                        notify,
+                        message,
+                        expires,
+                    };
+
+                    match batch::read_ids(&file_ids, &ids_from) {
+                        Err(err) => handle_error(err),
+
+                        Ok(file_ids) if file_ids.is_empty() => handle_error(batch::Error::NoIds),
+
+                        Ok(file_ids) if recursive => {
+                            let share_result = permissions::share::share_recursive(
+                                config,
+                                file_ids,
+                                dry_run,
+                                concurrency,
+                            )
+                            .await;
+
+                            share_result.unwrap_or_else(|err| {
+                                handle_error_with_exit_code(err.exit_code(), err)
+                            })
+                        }
+
+                        Ok(file_ids) if is_single_id(&file_ids, &ids_from) => {
+                            permissions::share(permissions::share::Config {
+                                file_id: file_ids[0].clone(),
+                                ..config
+                            })
+                            .await
+                            .unwrap_or_else(handle_error)
+                        }
+
+                        Ok(file_ids) => {
+                            permissions::share::share_many(config, file_ids, concurrency)
+                                .await
+                                .unwrap_or_else(|err| {
+                                    handle_error_with_exit_code(err.exit_code(), err)
+                                })
+                        }
+                    }
+                }
+
+                PermissionCommand::Update {
+                    file_id,
+                    id,
+                    email,
+                    role,
+                    expires,
+                    remove_expiration,
+                } => {
+                    let target = match (id, email) {
+                        (Some(id), _) => permissions::update::PermissionTarget::Id(id),
+                        (None, email) => {
+                            permissions::update::PermissionTarget::Email(email.unwrap_or_default())
+                        }
+                    };
+
+                    permissions::update(permissions::update::Config {
+                        file_id,
+                        target,
+                        role,
+                        expires,
+                        remove_expiration,
                     })
                     .await
                     .unwrap_or_else(handle_error)
@@ -685,12 +1618,14 @@ async fn main() {
 
                 PermissionCommand::List {
                     file_id,
+                    use_domain_admin_access,
                     skip_header,
                     field_separator,
                 } => {
                     // fmt
                     permissions::list(permissions::list::Config {
                         file_id,
+                        use_domain_admin_access,
                         skip_header,
                         field_separator,
                     })
@@ -698,18 +1633,133 @@ async fn main() {
                     .unwrap_or_else(handle_error)
                 }
 
-                PermissionCommand::Revoke { file_id, all, id } => {
+                PermissionCommand::Copy {
+                    source_id,
+                    file_ids,
+                    mirror,
+                    notify,
+                    ids_from,
+                    concurrency,
+                } => match batch::read_ids(&file_ids, &ids_from) {
+                    Err(err) => handle_error(err),
+
+                    Ok(file_ids) if file_ids.is_empty() => handle_error(batch::Error::NoIds),
+
+                    Ok(file_ids) => {
+                        // fmt
+                        permissions::copy(permissions::copy::Config {
+                            source_id,
+                            destination_ids: file_ids,
+                            mirror,
+                            notify,
+                            concurrency,
+                        })
+                        .await
+                        .unwrap_or_else(|err| handle_error_with_exit_code(err.exit_code(), err))
+                    }
+                },
+
+                PermissionCommand::Audit {
+                    folder,
+                    drive,
+                    domain,
+                    format,
+                    skip_header,
+                    field_separator,
+                    concurrency,
+                } => {
+                    // fmt
+                    permissions::audit(permissions::audit::Config {
+                        folder_id: folder,
+                        drive_id: drive,
+                        domain,
+                        format,
+                        skip_header,
+                        field_separator,
+                        concurrency,
+                    })
+                    .await
+                    .unwrap_or_else(|err| handle_error_with_exit_code(err.exit_code(), err))
+                }
+
+                PermissionCommand::Revoke {
+                    file_ids,
+                    all,
+                    id,
+                    email,
+                    domain,
+                    role,
+                    type_,
+                    recursive,
+                    dry_run,
+                    ids_from,
+                    concurrency,
+                } => {
+                    let filter = permissions::revoke::PermissionFilter {
+                        email,
+                        domain,
+                        role,
+                        type_,
+                    };
+
+                    let has_filter = filter.email.is_some()
+                        || filter.domain.is_some()
+                        || filter.role.is_some()
+                        || filter.type_.is_some();
+
                     let action = if all {
                         permissions::revoke::RevokeAction::AllExceptOwner
                     } else if id.is_some() {
                         permissions::revoke::RevokeAction::Id(id.unwrap_or_default())
+                    } else if has_filter {
+                        permissions::revoke::RevokeAction::Matching(filter)
                     } else {
                         permissions::revoke::RevokeAction::Anyone
                     };
 
-                    permissions::revoke(permissions::revoke::Config { file_id, action })
-                        .await
-                        .unwrap_or_else(handle_error)
+                    match batch::read_ids(&file_ids, &ids_from) {
+                        Err(err) => handle_error(err),
+
+                        Ok(file_ids) if file_ids.is_empty() => handle_error(batch::Error::NoIds),
+
+                        Ok(file_ids) if recursive => {
+                            let config = permissions::revoke::Config {
+                                file_id: String::new(),
+                                action,
+                            };
+
+                            permissions::revoke::revoke_recursive(
+                                config,
+                                file_ids,
+                                dry_run,
+                                concurrency,
+                            )
+                            .await
+                            .unwrap_or_else(|err| handle_error_with_exit_code(err.exit_code(), err))
+                        }
+
+                        Ok(file_ids) if is_single_id(&file_ids, &ids_from) => {
+                            permissions::revoke(permissions::revoke::Config {
+                                file_id: file_ids[0].clone(),
+                                action,
+                            })
+                            .await
+                            .unwrap_or_else(handle_error)
+                        }
+
+                        Ok(file_ids) => {
+                            let config = permissions::revoke::Config {
+                                file_id: String::new(),
+                                action,
+                            };
+
+                            permissions::revoke::revoke_many(config, file_ids, concurrency)
+                                .await
+                                .unwrap_or_else(|err| {
+                                    handle_error_with_exit_code(err.exit_code(), err)
+                                })
+                        }
+                    }
                 }
             }
         }
@@ -721,7 +1771,16 @@ async fn main() {
     }
 }
 
+// A single id given as argument keeps the output and exit codes of the single file commands
+fn is_single_id(ids: &[String], ids_from: &Option<PathBuf>) -> bool {
+    ids.len() == 1 && ids_from.is_none()
+}
+
 fn handle_error(err: impl Error) {
+    handle_error_with_exit_code(1, err)
+}
+
+fn handle_error_with_exit_code(exit_code: i32, err: impl Error) {
     eprintln!("Error: {}", err);
-    std::process::exit(1);
+    std::process::exit(exit_code);
 }
@@ -1,7 +1,14 @@
+pub mod audit;
+pub mod copy;
 pub mod list;
+pub mod recursive;
 pub mod revoke;
 pub mod share;
+pub mod update;
 
+pub use audit::audit;
+pub use copy::copy;
 pub use list::list;
 pub use revoke::revoke;
 pub use share::share;
+pub use update::update;
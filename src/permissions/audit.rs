@@ -0,0 +1,505 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_batch;
+use crate::common::failure_report::PARTIAL_FAILURE_EXIT_CODE;
+use crate::common::hub_helper;
+use crate::common::permission;
+use crate::common::table;
+use crate::common::table::Table;
+use crate::files;
+use crate::hub::Hub;
+use crate::permissions;
+use crate::permissions::recursive;
+use google_drive3::chrono;
+use google_drive3::chrono::DateTime;
+use serde::Serialize;
+use std::error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io;
+use std::str::FromStr;
+
+// Domains of consumer accounts, the users in them don't belong to one organization
+const CONSUMER_DOMAINS: [&str; 2] = ["gmail.com", "googlemail.com"];
+
+pub struct Config {
+    // The whole my drive is audited if neither a folder nor a shared drive is given
+    pub folder_id: Option<String>,
+    pub drive_id: Option<String>,
+    pub domain: Option<String>,
+    pub format: ReportFormat,
+    pub skip_header: bool,
+    pub field_separator: String,
+    pub concurrency: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum ReportFormat {
+    #[default]
+    Table,
+    Csv,
+    Json,
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReportFormat::Table => write!(f, "table"),
+            ReportFormat::Csv => write!(f, "csv"),
+            ReportFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(ReportFormat::Table),
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!(
+                "'{}' is not a valid format, valid values are: table, csv, json",
+                s
+            )),
+        }
+    }
+}
+
+// Who is considered internal: everyone in a workspace domain, or only the current user for
+// consumer accounts
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Internal {
+    Domain(String),
+    User(String),
+}
+
+// Why a permission is part of the report
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Exposure {
+    // Anyone with the link has access
+    Public,
+    // A user, group or domain outside of the audited domain has access
+    External,
+    // Everyone in the domain can find the file by searching
+    Discoverable,
+}
+
+impl Display for Exposure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Exposure::Public => write!(f, "public"),
+            Exposure::External => write!(f, "external"),
+            Exposure::Discoverable => write!(f, "discoverable"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub file_id: String,
+    pub path: String,
+    pub exposure: Exposure,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub role: String,
+    pub grantee: String,
+    pub expiration_time: Option<DateTime<chrono::Utc>>,
+    pub discoverable: bool,
+}
+
+pub async fn audit(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let internal = match &config.domain {
+        Some(domain) => Internal::Domain(domain.to_lowercase()),
+        None => get_internal(&hub, delegate_config).await?,
+    };
+
+    // The id of a shared drive is also the id of its root folder
+    let root_id = config
+        .folder_id
+        .clone()
+        .or(config.drive_id.clone())
+        .unwrap_or_else(|| String::from("root"));
+
+    let items = recursive::collect_items(&hub, &[root_id])
+        .await
+        .map_err(Error::CollectItems)?;
+
+    let mut findings = vec![];
+    let mut failed_count = 0;
+
+    for chunk in items.chunks(recursive::chunk_size(config.concurrency)) {
+        let item_ids: Vec<String> = chunk.iter().map(|item| item.id.clone()).collect();
+
+        let list_results = permissions::list::list_permissions_in_batch(
+            &hub,
+            &item_ids,
+            permissions::list::PERMISSION_LIST_FIELDS,
+            config.concurrency,
+        )
+        .await
+        .map_err(Error::DriveBatch)?;
+
+        for (item, result) in chunk.iter().zip(list_results) {
+            let permissions = match result {
+                Ok(permissions) => permissions,
+                Err(err) => {
+                    eprintln!(
+                        "Warning: Failed to list permissions of '{}': {}",
+                        item.label(),
+                        err
+                    );
+                    failed_count += 1;
+                    continue;
+                }
+            };
+
+            for permission in permissions {
+                if let Some(exposure) = get_exposure(&permission, &internal) {
+                    findings.push(Finding {
+                        file_id: item.id.clone(),
+                        path: item.label(),
+                        exposure,
+                        type_: permission.type_.clone().unwrap_or_default(),
+                        role: permission.role.clone().unwrap_or_default(),
+                        grantee: permissions::list::format_grantee(&permission),
+                        expiration_time: permission.expiration_time,
+                        discoverable: permission.allow_file_discovery.unwrap_or(false),
+                    });
+                }
+            }
+        }
+    }
+
+    print_report(&config, findings)?;
+
+    if failed_count > 0 {
+        return Err(Error::IncompleteAudit(failed_count, items.len()));
+    }
+
+    Ok(())
+}
+
+fn get_exposure(
+    permission: &google_drive3::api::Permission,
+    internal: &Internal,
+) -> Option<Exposure> {
+    let type_: permission::Type = permission.type_.clone().unwrap_or_default().parse().ok()?;
+    let discoverable = permission.allow_file_discovery.unwrap_or(false);
+
+    match type_ {
+        permission::Type::Anyone => Some(Exposure::Public),
+
+        permission::Type::Domain => {
+            let permission_domain = permission.domain.clone().unwrap_or_default();
+
+            let is_internal = match internal {
+                Internal::Domain(domain) => permission_domain.eq_ignore_ascii_case(domain),
+                Internal::User(_) => false,
+            };
+
+            if !is_internal {
+                Some(Exposure::External)
+            } else if discoverable {
+                Some(Exposure::Discoverable)
+            } else {
+                None
+            }
+        }
+
+        permission::Type::User | permission::Type::Group => {
+            // Deleted users and groups don't have an email address
+            let email = permission.email_address.clone()?;
+            let (_, email_domain) = email.rsplit_once('@')?;
+
+            let is_internal = match internal {
+                Internal::Domain(domain) => email_domain.eq_ignore_ascii_case(domain),
+                Internal::User(user_email) => email.eq_ignore_ascii_case(user_email),
+            };
+
+            if is_internal {
+                None
+            } else {
+                Some(Exposure::External)
+            }
+        }
+    }
+}
+
+// The domain of the current user, or the user alone if it is a consumer account
+async fn get_internal(hub: &Hub, delegate_config: UploadDelegateConfig) -> Result<Internal, Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+
+    let (_, about) = hub
+        .about()
+        .get()
+        .param("fields", "user(emailAddress)")
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .doit()
+        .await
+        .map_err(Error::About)?;
+
+    let email = about
+        .user
+        .and_then(|user| user.email_address)
+        .ok_or(Error::MissingUserDomain)?
+        .to_lowercase();
+
+    internal_for_email(&email).ok_or(Error::MissingUserDomain)
+}
+
+fn internal_for_email(email: &str) -> Option<Internal> {
+    let (_, domain) = email.rsplit_once('@')?;
+
+    if CONSUMER_DOMAINS.contains(&domain) {
+        Some(Internal::User(email.to_string()))
+    } else {
+        Some(Internal::Domain(domain.to_string()))
+    }
+}
+
+fn print_report(config: &Config, findings: Vec<Finding>) -> Result<(), Error> {
+    match config.format {
+        ReportFormat::Table => print_table(config, findings),
+        ReportFormat::Csv => print_csv(config, findings),
+
+        ReportFormat::Json => {
+            let json = serde_json::to_string_pretty(&findings).map_err(Error::SerializeReport)?;
+            println!("{}", json);
+        }
+    }
+
+    Ok(())
+}
+
+const REPORT_HEADER: [&str; 8] = [
+    "Id",
+    "Path",
+    "Exposure",
+    "Type",
+    "Role",
+    "Grantee",
+    "Expires",
+    "Discoverable",
+];
+
+fn report_values(finding: Finding) -> [String; 8] {
+    [
+        finding.file_id,
+        finding.path,
+        finding.exposure.to_string(),
+        finding.type_,
+        finding.role,
+        finding.grantee,
+        permissions::list::format_expiration(finding.expiration_time),
+        files::info::format_bool(finding.discoverable),
+    ]
+}
+
+fn print_table(config: &Config, findings: Vec<Finding>) {
+    let table = Table {
+        header: REPORT_HEADER,
+        values: findings.into_iter().map(report_values).collect(),
+    };
+
+    let _ = table::write(
+        io::stdout(),
+        table,
+        &table::DisplayConfig {
+            skip_header: config.skip_header,
+            separator: config.field_separator.clone(),
+        },
+    );
+}
+
+fn print_csv(config: &Config, findings: Vec<Finding>) {
+    if !config.skip_header {
+        println!("{}", to_csv_row(&REPORT_HEADER.map(String::from)));
+    }
+
+    for finding in findings {
+        println!("{}", to_csv_row(&report_values(finding)));
+    }
+}
+
+// Quotes the fields that contain separators, quotes or line breaks
+fn to_csv_row(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|value| {
+            if value.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    About(google_drive3::Error),
+    MissingUserDomain,
+    CollectItems(recursive::Error),
+    DriveBatch(drive_batch::Error),
+    SerializeReport(serde_json::Error),
+    IncompleteAudit(usize, usize),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::IncompleteAudit(_, _) => PARTIAL_FAILURE_EXIT_CODE,
+            _ => 1,
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::About(err) => write!(f, "Failed to get user info: {}", err),
+            Error::MissingUserDomain => {
+                write!(
+                    f,
+                    "Failed to find the domain of the current user, use the --domain option"
+                )
+            }
+            Error::CollectItems(err) => write!(f, "{}", err),
+            Error::DriveBatch(err) => write!(f, "{}", err),
+            Error::SerializeReport(err) => write!(f, "Failed to serialize report: {}", err),
+            Error::IncompleteAudit(failed, total) => {
+                write!(
+                    f,
+                    "Permissions of {} of {} items could not be audited",
+                    failed, total
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permission(
+        type_: &str,
+        email: Option<&str>,
+        domain: Option<&str>,
+    ) -> google_drive3::api::Permission {
+        google_drive3::api::Permission {
+            type_: Some(type_.to_string()),
+            role: Some(String::from("reader")),
+            email_address: email.map(String::from),
+            domain: domain.map(String::from),
+            ..google_drive3::api::Permission::default()
+        }
+    }
+
+    fn workspace() -> Internal {
+        Internal::Domain(String::from("example.com"))
+    }
+
+    #[test]
+    fn anyone_is_public() {
+        let anyone = permission("anyone", None, None);
+
+        assert_eq!(get_exposure(&anyone, &workspace()), Some(Exposure::Public));
+    }
+
+    #[test]
+    fn users_in_the_domain_are_internal() {
+        let user = permission("user", Some("Alice@Example.com"), None);
+        let group = permission("group", Some("team@example.com"), None);
+
+        assert_eq!(get_exposure(&user, &workspace()), None);
+        assert_eq!(get_exposure(&group, &workspace()), None);
+    }
+
+    #[test]
+    fn users_outside_of_the_domain_are_external() {
+        let user = permission("user", Some("alice@other.com"), None);
+        let subdomain = permission("user", Some("alice@sub.example.com"), None);
+
+        assert_eq!(get_exposure(&user, &workspace()), Some(Exposure::External));
+        assert_eq!(
+            get_exposure(&subdomain, &workspace()),
+            Some(Exposure::External)
+        );
+    }
+
+    #[test]
+    fn deleted_users_are_skipped() {
+        let deleted = permission("user", None, None);
+
+        assert_eq!(get_exposure(&deleted, &workspace()), None);
+    }
+
+    #[test]
+    fn domain_permissions_are_external_or_discoverable() {
+        let other = permission("domain", None, Some("other.com"));
+        let own = permission("domain", None, Some("example.com"));
+        let discoverable = google_drive3::api::Permission {
+            allow_file_discovery: Some(true),
+            ..own.clone()
+        };
+
+        assert_eq!(get_exposure(&other, &workspace()), Some(Exposure::External));
+        assert_eq!(get_exposure(&own, &workspace()), None);
+        assert_eq!(
+            get_exposure(&discoverable, &workspace()),
+            Some(Exposure::Discoverable)
+        );
+    }
+
+    #[test]
+    fn other_consumer_users_are_external() {
+        let internal = internal_for_email("me@gmail.com").unwrap();
+        let me = permission("user", Some("Me@gmail.com"), None);
+        let other = permission("user", Some("other@gmail.com"), None);
+
+        assert_eq!(internal, Internal::User(String::from("me@gmail.com")));
+        assert_eq!(get_exposure(&me, &internal), None);
+        assert_eq!(get_exposure(&other, &internal), Some(Exposure::External));
+    }
+
+    #[test]
+    fn workspace_users_are_internal_to_their_domain() {
+        assert_eq!(internal_for_email("me@example.com"), Some(workspace()));
+        assert_eq!(
+            internal_for_email("me@googlemail.com"),
+            Some(Internal::User(String::from("me@googlemail.com")))
+        );
+        assert_eq!(internal_for_email("invalid"), None);
+    }
+
+    #[test]
+    fn csv_row_quotes_special_values() {
+        let values = [
+            String::from("plain"),
+            String::from("a,b"),
+            String::from("say \"hi\""),
+            String::from("two\nlines"),
+            String::new(),
+        ];
+
+        assert_eq!(
+            to_csv_row(&values),
+            "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\","
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use crate::common::batch;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_batch;
+use crate::common::drive_batch::Call;
+use crate::common::hub_helper;
+use crate::common::permission;
+use crate::permissions;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+// Permissions with details, so inherited permissions can be told apart from the ones set on the file
+const COPY_PERMISSION_FIELDS: &str = "permissions(id,role,type,domain,emailAddress,allowFileDiscovery,expirationTime,deleted,permissionDetails(inherited))";
+
+pub struct Config {
+    pub source_id: String,
+    pub destination_ids: Vec<String>,
+    // Revoke the permissions of the destinations that the source doesn't have
+    pub mirror: bool,
+    pub notify: bool,
+    pub concurrency: usize,
+}
+
+pub async fn copy(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let source_permissions = permissions::list::list_permissions_with_fields(
+        &hub,
+        delegate_config,
+        &config.source_id,
+        COPY_PERMISSION_FIELDS,
+    )
+    .await
+    .map_err(Error::ListSourcePermissions)?;
+
+    let source_permissions: Vec<google_drive3::api::Permission> =
+        source_permissions.into_iter().filter(is_copyable).collect();
+
+    let source_keys: HashSet<GranteeKey> = source_permissions.iter().map(grantee_key).collect();
+
+    let mut summary = batch::Summary::default();
+
+    let get_calls: Vec<Call> = config
+        .destination_ids
+        .iter()
+        .map(|file_id| Call::get_file(file_id, "id,name"))
+        .collect();
+
+    let get_results = drive_batch::execute(&hub, &get_calls, config.concurrency)
+        .await
+        .map_err(Error::DriveBatch)?;
+
+    let list_results = permissions::list::list_permissions_in_batch(
+        &hub,
+        &config.destination_ids,
+        COPY_PERMISSION_FIELDS,
+        config.concurrency,
+    )
+    .await
+    .map_err(Error::DriveBatch)?;
+
+    let mut destinations = vec![];
+    let mut changes = vec![];
+
+    for ((file_id, file_result), permissions_result) in config
+        .destination_ids
+        .iter()
+        .zip(get_results)
+        .zip(list_results)
+    {
+        let file = match drive_batch::parse::<google_drive3::api::File>(file_result) {
+            Ok(file) => file,
+            Err(err) => {
+                summary.add_failure(file_id, &err);
+                continue;
+            }
+        };
+
+        let permissions = match permissions_result {
+            Ok(permissions) => permissions,
+            Err(err) => {
+                summary.add_failure(file_id, &err);
+                continue;
+            }
+        };
+
+        let destination_index = destinations.len();
+
+        // The owner is included, so it is neither granted again nor revoked
+        let existing_permissions: HashMap<GranteeKey, google_drive3::api::Permission> = permissions
+            .into_iter()
+            .filter(|p| !p.deleted.unwrap_or(false) && !is_inherited(p))
+            .map(|p| (grantee_key(&p), p))
+            .collect();
+
+        for permission in &source_permissions {
+            match existing_permissions.get(&grantee_key(permission)) {
+                None => {
+                    changes.push((destination_index, Change::Grant(permission.clone())));
+                }
+
+                Some(existing) if existing.role != permission.role && !is_owner(existing) => {
+                    let role = permission.role.clone().unwrap_or_default();
+                    changes.push((destination_index, Change::Update(existing.clone(), role)));
+                }
+
+                Some(_) => {}
+            }
+        }
+
+        if config.mirror {
+            let extra_permissions = existing_permissions
+                .into_values()
+                .filter(is_copyable)
+                .filter(|p| !source_keys.contains(&grantee_key(p)));
+
+            for permission in extra_permissions {
+                changes.push((destination_index, Change::Revoke(permission)));
+            }
+        }
+
+        destinations.push((file_id.clone(), file));
+    }
+
+    let calls = changes
+        .iter()
+        .map(|(destination_index, change)| {
+            let (file_id, _) = &destinations[*destination_index];
+            change_call(file_id, change, config.notify)
+        })
+        .collect::<Result<Vec<Call>, drive_batch::Error>>()
+        .map_err(Error::DriveBatch)?;
+
+    let results = drive_batch::execute(&hub, &calls, config.concurrency)
+        .await
+        .map_err(Error::DriveBatch)?;
+
+    // A destination only succeeds if all its permissions were changed
+    let mut destination_errors: Vec<Option<ChangeError>> =
+        destinations.iter().map(|_| None).collect();
+
+    for ((destination_index, change), result) in changes.into_iter().zip(results) {
+        let (_, file) = &destinations[destination_index];
+
+        match result {
+            Ok(_) => print_change_details(file, &change),
+            Err(err) => destination_errors[destination_index] = Some(ChangeError(change, err)),
+        }
+    }
+
+    for ((file_id, _), err) in destinations.into_iter().zip(destination_errors) {
+        match err {
+            Some(err) => summary.add_failure(&file_id, &err),
+            None => summary.add_success(),
+        }
+    }
+
+    summary.finish().map_err(Error::Batch)
+}
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    Grant(google_drive3::api::Permission),
+    // The existing permission of the destination and the role of the source
+    Update(google_drive3::api::Permission, String),
+    Revoke(google_drive3::api::Permission),
+}
+
+impl Change {
+    fn permission(&self) -> &google_drive3::api::Permission {
+        match self {
+            Change::Grant(permission) => permission,
+            Change::Update(permission, _) => permission,
+            Change::Revoke(permission) => permission,
+        }
+    }
+}
+
+fn change_call(file_id: &str, change: &Change, notify: bool) -> Result<Call, drive_batch::Error> {
+    match change {
+        Change::Grant(permission) => {
+            let new_permission = google_drive3::api::Permission {
+                role: permission.role.clone(),
+                type_: permission.type_.clone(),
+                email_address: permission.email_address.clone(),
+                domain: permission.domain.clone(),
+                allow_file_discovery: permission.allow_file_discovery,
+                expiration_time: permission.expiration_time,
+                ..google_drive3::api::Permission::default()
+            };
+
+            let notify = notify.to_string();
+            let mut query = vec![("fields", "id")];
+
+            // Drive only sends notification emails to users and groups
+            if supports_notification(permission) {
+                query.push(("sendNotificationEmail", notify.as_str()));
+            }
+
+            Call::create_permission(file_id, &new_permission, &query)
+        }
+
+        // Only the role is changed, the grantee keeps the expiration it has on the destination
+        Change::Update(permission, role) => {
+            let updated_permission = google_drive3::api::Permission {
+                role: Some(role.clone()),
+                ..google_drive3::api::Permission::default()
+            };
+
+            Call::update_permission(
+                file_id,
+                &permission.id.clone().unwrap_or_default(),
+                &updated_permission,
+                &[("fields", "id")],
+            )
+        }
+
+        Change::Revoke(permission) => Ok(Call::delete_permission(
+            file_id,
+            &permission.id.clone().unwrap_or_default(),
+        )),
+    }
+}
+
+fn supports_notification(permission: &google_drive3::api::Permission) -> bool {
+    permission
+        .type_
+        .clone()
+        .unwrap_or_default()
+        .parse::<permission::Type>()
+        .map(|type_| type_.supports_notification())
+        .unwrap_or(false)
+}
+
+// The owner can't be copied, and inherited permissions come from the parent folders
+fn is_copyable(permission: &google_drive3::api::Permission) -> bool {
+    let is_deleted = permission.deleted.unwrap_or(false);
+    !is_owner(permission) && !is_deleted && !is_inherited(permission)
+}
+
+fn is_owner(permission: &google_drive3::api::Permission) -> bool {
+    permission.role == Some(permission::Role::Owner.to_string())
+}
+
+fn is_inherited(permission: &google_drive3::api::Permission) -> bool {
+    permission
+        .permission_details
+        .as_ref()
+        .is_some_and(|details| {
+            !details.is_empty() && details.iter().all(|detail| detail.inherited == Some(true))
+        })
+}
+
+// Identifies the grantee of a permission, independent of its id and role
+type GranteeKey = (String, String);
+
+fn grantee_key(permission: &google_drive3::api::Permission) -> GranteeKey {
+    let grantee = permission
+        .email_address
+        .clone()
+        .or_else(|| permission.domain.clone())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    (permission.type_.clone().unwrap_or_default(), grantee)
+}
+
+fn print_change_details(file: &google_drive3::api::File, change: &Change) {
+    let permission = change.permission();
+
+    let description = permissions::revoke::permission_description(permission)
+        .unwrap_or_else(|_| format!("permission '{}'", permission.id.clone().unwrap_or_default()));
+
+    let file_name = file.name.clone().unwrap_or_default();
+
+    match change {
+        Change::Grant(_) => println!("Granted {} for '{}'", description, file_name),
+        Change::Update(_, role) => {
+            println!("Changed {} to '{}' for '{}'", description, role, file_name)
+        }
+        Change::Revoke(_) => println!("Revoked {} for '{}'", description, file_name),
+    }
+}
+
+#[derive(Debug)]
+pub struct ChangeError(Change, drive_batch::CallError);
+
+impl error::Error for ChangeError {}
+
+impl Display for ChangeError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            ChangeError(Change::Grant(permission), err) => {
+                write!(
+                    f,
+                    "Failed to grant permission to '{}': {}",
+                    permissions::list::format_grantee(permission),
+                    err
+                )
+            }
+            ChangeError(Change::Update(permission, role), err) => {
+                write!(
+                    f,
+                    "Failed to change permission '{}' to role '{}': {}",
+                    permission.id.clone().unwrap_or_default(),
+                    role,
+                    err
+                )
+            }
+            ChangeError(Change::Revoke(permission), err) => {
+                write!(
+                    f,
+                    "Failed to revoke permission '{}': {}",
+                    permission.id.clone().unwrap_or_default(),
+                    err
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    ListSourcePermissions(google_drive3::Error),
+    DriveBatch(drive_batch::Error),
+    Batch(batch::Error),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Batch(err) => err.exit_code(),
+            _ => 1,
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::ListSourcePermissions(err) => {
+                write!(f, "Failed to list permissions of the source: {}", err)
+            }
+            Error::DriveBatch(err) => write!(f, "{}", err),
+            Error::Batch(err) => write!(f, "{}", err),
+        }
+    }
+}
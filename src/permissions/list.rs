@@ -1,55 +1,107 @@
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_batch;
+use crate::common::drive_batch::Call;
 use crate::common::hub_helper;
 use crate::common::table;
 use crate::common::table::Table;
 use crate::files;
 use crate::hub::Hub;
+use google_drive3::chrono::DateTime;
+use google_drive3::chrono::Utc;
+use serde::Deserialize;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::io;
 
+pub const PERMISSION_LIST_FIELDS: &str =
+    "permissions(id,role,type,domain,emailAddress,allowFileDiscovery,expirationTime)";
+
+// Fields shown by the list command, including the details of shared drive permissions
+const PERMISSION_DETAIL_FIELDS: &str = "nextPageToken,permissions(id,role,type,domain,emailAddress,displayName,allowFileDiscovery,expirationTime,deleted,pendingOwner,permissionDetails(permissionType,role,inherited,inheritedFrom))";
+
+const MAX_PAGE_SIZE: &str = "100";
+
 #[derive(Clone, Debug)]
 pub struct Config {
     pub file_id: String,
+    pub use_domain_admin_access: bool,
     pub skip_header: bool,
     pub field_separator: String,
 }
 
+// The generated client predates the pendingOwner field, so it is deserialized next to the permission
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetailedPermission {
+    #[serde(flatten)]
+    pub permission: google_drive3::api::Permission,
+    pub pending_owner: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DetailedPermissionList {
+    next_page_token: Option<String>,
+    permissions: Option<Vec<DetailedPermission>>,
+}
+
 pub async fn list(config: Config) -> Result<(), Error> {
     let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
-    let delegate_config = UploadDelegateConfig::default();
 
-    files::info::get_file(&hub, &config.file_id)
-        .await
-        .map_err(Error::GetFile)?;
+    // Administrators may not have access to the file itself
+    if !config.use_domain_admin_access {
+        files::info::get_file(&hub, &config.file_id)
+            .await
+            .map_err(Error::GetFile)?;
+    }
 
-    let permissions = list_permissions(&hub, delegate_config, &config.file_id)
-        .await
-        .map_err(Error::ListPermissions)?;
+    let permissions =
+        list_permission_details(&hub, &config.file_id, config.use_domain_admin_access).await?;
 
     print_permissions_table(&config, permissions);
 
     Ok(())
 }
 
-fn print_permissions_table(config: &Config, permissions: Vec<google_drive3::api::Permission>) {
-    let mut values: Vec<[String; 6]> = vec![];
+fn print_permissions_table(config: &Config, permissions: Vec<DetailedPermission>) {
+    let mut values: Vec<[String; 12]> = vec![];
+
+    for detailed in permissions {
+        let permission = detailed.permission;
 
-    for permission in permissions {
         values.push([
-            permission.id.unwrap_or_default(),
-            permission.type_.unwrap_or_default(),
-            permission.role.unwrap_or_default(),
-            permission.email_address.unwrap_or_default(),
-            permission.domain.unwrap_or_default(),
+            permission.id.clone().unwrap_or_default(),
+            permission.type_.clone().unwrap_or_default(),
+            permission.role.clone().unwrap_or_default(),
+            permission.display_name.clone().unwrap_or_default(),
+            permission.email_address.clone().unwrap_or_default(),
+            permission.domain.clone().unwrap_or_default(),
             files::info::format_bool(permission.allow_file_discovery.unwrap_or_default()),
+            format_expiration(permission.expiration_time),
+            files::info::format_bool(detailed.pending_owner.unwrap_or_default()),
+            files::info::format_bool(permission.deleted.unwrap_or_default()),
+            format_inherited_from(&permission),
+            format_details(&permission),
         ])
     }
 
     let table = Table {
-        header: ["Id", "Type", "Role", "Email", "Domain", "Discoverable"],
+        header: [
+            "Id",
+            "Type",
+            "Role",
+            "Name",
+            "Email",
+            "Domain",
+            "Discoverable",
+            "Expires",
+            "Pending owner",
+            "Deleted",
+            "Inherited from",
+            "Details",
+        ],
         values,
     };
 
@@ -63,34 +115,210 @@ fn print_permissions_table(config: &Config, permissions: Vec<google_drive3::api:
     );
 }
 
+// Ids of the folders the permission is inherited from, only set for shared drive items
+fn format_inherited_from(permission: &google_drive3::api::Permission) -> String {
+    let mut folder_ids: Vec<String> = permission
+        .permission_details
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|detail| detail.inherited.unwrap_or(false))
+        .filter_map(|detail| detail.inherited_from)
+        .collect();
+
+    folder_ids.sort();
+    folder_ids.dedup();
+    folder_ids.join(",")
+}
+
+// Each of the ways the grantee has access, like "member:writer (inherited)"
+fn format_details(permission: &google_drive3::api::Permission) -> String {
+    permission
+        .permission_details
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|detail| {
+            let access = if detail.inherited.unwrap_or(false) {
+                "inherited"
+            } else {
+                "direct"
+            };
+
+            format!(
+                "{}:{} ({})",
+                detail.permission_type.unwrap_or_default(),
+                detail.role.unwrap_or_default(),
+                access
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Email address or domain of the grantee, or the type for permissions without either
+pub fn format_grantee(permission: &google_drive3::api::Permission) -> String {
+    permission
+        .email_address
+        .clone()
+        .or_else(|| permission.domain.clone())
+        .unwrap_or_else(|| permission.type_.clone().unwrap_or_default())
+}
+
+pub fn format_expiration(expiration_time: Option<DateTime<Utc>>) -> String {
+    expiration_time
+        .map(files::info::format_date_time)
+        .unwrap_or_default()
+}
+
+// Lists the permissions with all details. The request is sent without the generated client,
+// which would drop the pendingOwner field of the response
+pub async fn list_permission_details(
+    hub: &Hub,
+    file_id: &str,
+    use_domain_admin_access: bool,
+) -> Result<Vec<DetailedPermission>, Error> {
+    let mut collected_permissions = vec![];
+    let mut page_token: Option<String> = None;
+    let use_domain_admin_access = use_domain_admin_access.to_string();
+
+    loop {
+        let mut query = vec![
+            ("fields", PERMISSION_DETAIL_FIELDS),
+            ("pageSize", MAX_PAGE_SIZE),
+            ("useDomainAdminAccess", use_domain_admin_access.as_str()),
+        ];
+
+        if let Some(token) = &page_token {
+            query.push(("pageToken", token.as_str()));
+        }
+
+        let call = Call::list_permissions(file_id, &query);
+
+        let result = drive_batch::send(hub, &call)
+            .await
+            .map_err(Error::DriveBatch)?;
+
+        let permission_list = drive_batch::parse::<DetailedPermissionList>(result)
+            .map_err(Error::ListPermissionDetails)?;
+
+        collected_permissions.extend(permission_list.permissions.unwrap_or_default());
+
+        match permission_list.next_page_token {
+            Some(next_page_token) => page_token = Some(next_page_token),
+            None => return Ok(collected_permissions),
+        }
+    }
+}
+
+pub type PermissionsResult = Result<Vec<google_drive3::api::Permission>, drive_batch::CallError>;
+
+// Lists the permissions of each of the files with the batch api. The first pages are fetched
+// together, files with more permissions than fit on a page are followed up until all pages are listed
+pub async fn list_permissions_in_batch(
+    hub: &Hub,
+    file_ids: &[String],
+    fields: &str,
+    concurrency: usize,
+) -> Result<Vec<PermissionsResult>, drive_batch::Error> {
+    let fields = format!("nextPageToken,{}", fields);
+    let mut results: Vec<PermissionsResult> = file_ids.iter().map(|_| Ok(vec![])).collect();
+
+    // Index of the file and the token of the page to fetch next
+    let mut pending: Vec<(usize, Option<String>)> =
+        (0..file_ids.len()).map(|index| (index, None)).collect();
+
+    while !pending.is_empty() {
+        let calls: Vec<Call> = pending
+            .iter()
+            .map(|(index, page_token)| {
+                let mut query = vec![("fields", fields.as_str()), ("pageSize", MAX_PAGE_SIZE)];
+
+                if let Some(token) = page_token {
+                    query.push(("pageToken", token.as_str()));
+                }
+
+                Call::list_permissions(&file_ids[*index], &query)
+            })
+            .collect();
+
+        let call_results = drive_batch::execute(hub, &calls, concurrency).await?;
+        let mut next_pending = vec![];
+
+        for ((index, _), result) in pending.into_iter().zip(call_results) {
+            match drive_batch::parse::<google_drive3::api::PermissionList>(result) {
+                Ok(list) => {
+                    if let Ok(permissions) = &mut results[index] {
+                        permissions.extend(list.permissions.unwrap_or_default());
+                    }
+
+                    if let Some(token) = list.next_page_token {
+                        next_pending.push((index, Some(token)));
+                    }
+                }
+
+                Err(err) => results[index] = Err(err),
+            }
+        }
+
+        pending = next_pending;
+    }
+
+    Ok(results)
+}
+
 pub async fn list_permissions(
     hub: &Hub,
     delegate_config: UploadDelegateConfig,
     file_id: &str,
 ) -> Result<Vec<google_drive3::api::Permission>, google_drive3::Error> {
-    let mut delegate = UploadDelegate::new(delegate_config);
-
-    let (_, permission_list) = hub
-        .permissions()
-        .list(file_id)
-        .param(
-            "fields",
-            "permissions(id,role,type,domain,emailAddress,allowFileDiscovery)",
-        )
-        .add_scope(google_drive3::api::Scope::Full)
-        .delegate(&mut delegate)
-        .supports_all_drives(true)
-        .doit()
-        .await?;
-
-    Ok(permission_list.permissions.unwrap_or_default())
+    list_permissions_with_fields(hub, delegate_config, file_id, PERMISSION_LIST_FIELDS).await
+}
+
+// Lists the permissions of the file with the given permission fields, following all pages.
+// Permissions of shared drive items are paged
+pub async fn list_permissions_with_fields(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    file_id: &str,
+    fields: &str,
+) -> Result<Vec<google_drive3::api::Permission>, google_drive3::Error> {
+    let mut collected_permissions = vec![];
+    let mut page_token: Option<String> = None;
+    let fields = format!("nextPageToken,{}", fields);
+
+    loop {
+        let mut delegate = UploadDelegate::new(delegate_config.clone());
+        let mut req = hub.permissions().list(file_id);
+
+        if let Some(token) = &page_token {
+            req = req.page_token(token);
+        }
+
+        let (_, permission_list) = req
+            .param("pageSize", MAX_PAGE_SIZE)
+            .param("fields", &fields)
+            .add_scope(google_drive3::api::Scope::Full)
+            .delegate(&mut delegate)
+            .supports_all_drives(true)
+            .doit()
+            .await?;
+
+        collected_permissions.extend(permission_list.permissions.unwrap_or_default());
+
+        match permission_list.next_page_token {
+            Some(next_page_token) => page_token = Some(next_page_token),
+            None => return Ok(collected_permissions),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum Error {
     Hub(hub_helper::Error),
     GetFile(google_drive3::Error),
-    ListPermissions(google_drive3::Error),
+    DriveBatch(drive_batch::Error),
+    ListPermissionDetails(drive_batch::CallError),
 }
 
 impl error::Error for Error {}
@@ -102,7 +330,8 @@ impl Display for Error {
             Error::GetFile(err) => {
                 write!(f, "Failed to get file: {}", err)
             }
-            Error::ListPermissions(err) => {
+            Error::DriveBatch(err) => write!(f, "{}", err),
+            Error::ListPermissionDetails(err) => {
                 write!(f, "Failed to list permissions: {}", err)
             }
         }
@@ -0,0 +1,115 @@
+use crate::common::batch;
+use crate::common::drive_batch;
+use crate::common::drive_file;
+use crate::common::file_tree_drive;
+use crate::common::file_tree_drive::FileTreeDrive;
+use crate::common::file_tree_drive::TreeOptions;
+use crate::common::hub_helper;
+use crate::files;
+use crate::hub::Hub;
+use std::cmp::max;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::path::PathBuf;
+
+// A file or folder in one of the trees, the path starts with the name of the root folder
+#[derive(Debug, Clone)]
+pub struct TreeItem {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+impl TreeItem {
+    pub fn label(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+// Collects the items of the trees below each of the files. Folders come before
+// their content, so permission changes to a folder are applied before its children.
+// Documents and shortcuts are included, they have permissions like any other file
+pub async fn collect_items(hub: &Hub, file_ids: &[String]) -> Result<Vec<TreeItem>, Error> {
+    let mut items = vec![];
+
+    for file_id in file_ids {
+        let file = files::info::get_file(hub, file_id)
+            .await
+            .map_err(|err| Error::GetFile(file_id.clone(), err))?;
+
+        if !drive_file::is_directory(&file) {
+            items.push(TreeItem {
+                id: file_id.clone(),
+                path: PathBuf::from(file.name.unwrap_or_default()),
+            });
+
+            continue;
+        }
+
+        let tree_options = TreeOptions {
+            include_documents: true,
+            ..TreeOptions::default()
+        };
+
+        let tree = FileTreeDrive::from_file_with_options(hub, &file, &tree_options)
+            .await
+            .map_err(Error::CreateFileTree)?;
+
+        for folder in tree.folders() {
+            items.push(TreeItem {
+                id: folder.drive_id.clone(),
+                path: folder.relative_path(),
+            });
+
+            for file in folder.files() {
+                items.push(TreeItem {
+                    id: file.drive_id.clone(),
+                    path: file.relative_path(),
+                });
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+// Number of items handled between progress updates
+pub fn chunk_size(concurrency: usize) -> usize {
+    drive_batch::MAX_BATCH_SIZE * max(concurrency, 1)
+}
+
+pub fn print_progress(done: usize, total: usize, message: &str) {
+    println!("[{}/{}] {}", done, total, message);
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    GetFile(String, google_drive3::Error),
+    CreateFileTree(file_tree_drive::Error),
+    Batch(batch::Error),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Batch(err) => err.exit_code(),
+            _ => 1,
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::GetFile(file_id, err) => {
+                write!(f, "Failed to get file '{}': {}", file_id, err)
+            }
+            Error::CreateFileTree(err) => write!(f, "Failed to create file tree: {}", err),
+            Error::Batch(err) => write!(f, "{}", err),
+        }
+    }
+}
@@ -1,10 +1,14 @@
+use crate::common::batch;
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_batch;
+use crate::common::drive_batch::Call;
 use crate::common::hub_helper;
 use crate::common::permission;
 use crate::files;
 use crate::hub::Hub;
 use crate::permissions;
+use crate::permissions::recursive;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -18,13 +22,268 @@ pub struct Config {
 pub async fn revoke(config: Config) -> Result<(), Error> {
     let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
+    revoke_permissions(&hub, delegate_config, &config).await
+}
+
+// Revokes the permissions of each of the files, the file id of the config is ignored.
+// The calls are sent with the batch api to keep the number of requests low
+pub async fn revoke_many(
+    config: Config,
+    file_ids: Vec<String>,
+    concurrency: usize,
+) -> Result<(), batch::Error> {
+    let hub = hub_helper::get_hub().await.map_err(batch::Error::Hub)?;
+    let mut summary = batch::Summary::default();
+
+    let get_calls: Vec<Call> = file_ids
+        .iter()
+        .map(|file_id| Call::get_file(file_id, "id,name"))
+        .collect();
+
+    let get_results = drive_batch::execute(&hub, &get_calls, concurrency)
+        .await
+        .map_err(batch::Error::DriveBatch)?;
+
+    let list_results = permissions::list::list_permissions_in_batch(
+        &hub,
+        &file_ids,
+        permissions::list::PERMISSION_LIST_FIELDS,
+        concurrency,
+    )
+    .await
+    .map_err(batch::Error::DriveBatch)?;
+
+    let mut files = vec![];
+    let mut permissions_to_delete = vec![];
+
+    for ((file_id, file_result), permissions_result) in
+        file_ids.into_iter().zip(get_results).zip(list_results)
+    {
+        let file = match drive_batch::parse::<google_drive3::api::File>(file_result) {
+            Ok(file) => file,
+            Err(err) => {
+                summary.add_failure(&file_id, &err);
+                continue;
+            }
+        };
+
+        let permissions = match permissions_result {
+            Ok(permissions) => permissions,
+            Err(err) => {
+                summary.add_failure(&file_id, &err);
+                continue;
+            }
+        };
+
+        match config.action.get_matching_permissions(permissions) {
+            Ok(matching) => {
+                for permission in matching {
+                    permissions_to_delete.push((files.len(), permission));
+                }
+
+                files.push((file_id, file));
+            }
+
+            Err(err) => summary.add_failure(&file_id, &err),
+        }
+    }
+
+    let delete_calls: Vec<Call> = permissions_to_delete
+        .iter()
+        .map(|(file_index, permission)| {
+            Call::delete_permission(
+                &files[*file_index].0,
+                &permission.id.clone().unwrap_or_default(),
+            )
+        })
+        .collect();
+
+    let delete_results = drive_batch::execute(&hub, &delete_calls, concurrency)
+        .await
+        .map_err(batch::Error::DriveBatch)?;
+
+    // A file only succeeds if all its permissions were revoked
+    let mut file_errors: Vec<Option<Error>> = files.iter().map(|_| None).collect();
+
+    for ((file_index, permission), result) in permissions_to_delete.into_iter().zip(delete_results)
+    {
+        let (_, file) = &files[file_index];
+
+        match result {
+            // The permission is already revoked when the result is printed
+            Ok(_) => match permission_description(&permission) {
+                Ok(description) => println!(
+                    "Revoked {} for '{}'",
+                    description,
+                    file.name.clone().unwrap_or_default()
+                ),
+
+                Err(_) => println!(
+                    "Revoked permission with id: '{}'",
+                    permission.id.clone().unwrap_or_default()
+                ),
+            },
+
+            Err(err) => {
+                file_errors[file_index] = Some(Error::DeletePermissionInBatch(permission, err));
+            }
+        }
+    }
+
+    for ((file_id, _), err) in files.into_iter().zip(file_errors) {
+        match err {
+            Some(err) => summary.add_failure(&file_id, &err),
+            None => summary.add_success(),
+        }
+    }
+
+    summary.finish()
+}
+
+// Revokes the matching permissions of the files and everything below the folders among them,
+// the file id of the config is ignored. Items without matching permissions are skipped.
+// With dry run the permissions are listed but not revoked
+pub async fn revoke_recursive(
+    config: Config,
+    file_ids: Vec<String>,
+    dry_run: bool,
+    concurrency: usize,
+) -> Result<(), recursive::Error> {
+    let hub = hub_helper::get_hub().await.map_err(recursive::Error::Hub)?;
+    let items = recursive::collect_items(&hub, &file_ids).await?;
+    let total = items.len();
+
+    let mut summary = batch::Summary::default();
+    let mut done = 0;
+
+    for chunk in items.chunks(recursive::chunk_size(concurrency)) {
+        let item_ids: Vec<String> = chunk.iter().map(|item| item.id.clone()).collect();
 
-    let file = files::info::get_file(&hub, &config.file_id)
+        let list_results = permissions::list::list_permissions_in_batch(
+            &hub,
+            &item_ids,
+            permissions::list::PERMISSION_LIST_FIELDS,
+            concurrency,
+        )
+        .await
+        .map_err(|err| recursive::Error::Batch(batch::Error::DriveBatch(err)))?;
+
+        let mut listed_items = vec![];
+        let mut permissions_to_delete = vec![];
+
+        for (item, result) in chunk.iter().zip(list_results) {
+            let permissions = match result {
+                Ok(permissions) => permissions,
+                Err(err) => {
+                    done += 1;
+                    summary.add_failure(&item.label(), &err);
+                    continue;
+                }
+            };
+
+            match config.action.get_matching_permissions(permissions) {
+                Ok(matching) => {
+                    for permission in matching {
+                        permissions_to_delete.push((listed_items.len(), permission));
+                    }
+
+                    listed_items.push(item);
+                }
+
+                // Only some of the items are expected to have the permission
+                Err(Error::PermissionNotFound(_)) | Err(Error::NoMatchingPermissions(_)) => {
+                    listed_items.push(item)
+                }
+
+                Err(err) => {
+                    done += 1;
+                    summary.add_failure(&item.label(), &err);
+                }
+            }
+        }
+
+        let delete_results = if dry_run {
+            permissions_to_delete
+                .iter()
+                .map(|_| Ok(serde_json::Value::Null))
+                .collect()
+        } else {
+            let delete_calls: Vec<Call> = permissions_to_delete
+                .iter()
+                .map(|(item_index, permission)| {
+                    Call::delete_permission(
+                        &listed_items[*item_index].id,
+                        &permission.id.clone().unwrap_or_default(),
+                    )
+                })
+                .collect();
+
+            drive_batch::execute(&hub, &delete_calls, concurrency)
+                .await
+                .map_err(|err| recursive::Error::Batch(batch::Error::DriveBatch(err)))?
+        };
+
+        let mut item_messages: Vec<Vec<String>> = listed_items.iter().map(|_| vec![]).collect();
+        let mut item_errors: Vec<Option<Error>> = listed_items.iter().map(|_| None).collect();
+
+        for ((item_index, permission), result) in
+            permissions_to_delete.into_iter().zip(delete_results)
+        {
+            let description = permission_description(&permission).unwrap_or_else(|_| {
+                format!("permission '{}'", permission.id.clone().unwrap_or_default())
+            });
+
+            match result {
+                // Inherited permissions disappear when they are revoked from the parent folder
+                Err(err) if err.status != Some(404) => {
+                    item_errors[item_index] = Some(Error::DeletePermissionInBatch(permission, err));
+                }
+
+                _ if dry_run => {
+                    item_messages[item_index].push(format!("Would revoke {}", description))
+                }
+
+                _ => item_messages[item_index].push(format!("Revoked {}", description)),
+            }
+        }
+
+        for ((item, messages), err) in listed_items.into_iter().zip(item_messages).zip(item_errors)
+        {
+            done += 1;
+
+            if let Some(err) = err {
+                summary.add_failure(&item.label(), &err);
+                continue;
+            }
+
+            if messages.is_empty() {
+                let message = format!("No matching permissions for '{}'", item.label());
+                recursive::print_progress(done, total, &message);
+            }
+
+            for message in messages {
+                let message = format!("{} for '{}'", message, item.label());
+                recursive::print_progress(done, total, &message);
+            }
+
+            summary.add_success();
+        }
+    }
+
+    summary.finish().map_err(recursive::Error::Batch)
+}
+
+pub async fn revoke_permissions(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    config: &Config,
+) -> Result<(), Error> {
+    let file = files::info::get_file(hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
     let permissions =
-        permissions::list::list_permissions(&hub, delegate_config.clone(), &config.file_id)
+        permissions::list::list_permissions(hub, delegate_config.clone(), &config.file_id)
             .await
             .map_err(Error::ListPermissions)?;
 
@@ -39,7 +298,7 @@ pub async fn revoke(config: Config) -> Result<(), Error> {
         }
 
         delete_permission(
-            &hub,
+            hub,
             delegate_config.clone(),
             &config.file_id,
             &permission.id.clone().unwrap_or_default(),
@@ -80,7 +339,9 @@ pub enum Error {
     GetFile(google_drive3::Error),
     ListPermissions(google_drive3::Error),
     DeletePermission(google_drive3::api::Permission, google_drive3::Error),
+    DeletePermissionInBatch(google_drive3::api::Permission, drive_batch::CallError),
     PermissionNotFound(String),
+    NoMatchingPermissions(PermissionFilter),
     UnknownPermissionType(String),
     UnknownPermissionRole(String),
 }
@@ -105,9 +366,20 @@ impl Display for Error {
                     err
                 )
             }
+            Error::DeletePermissionInBatch(permission, err) => {
+                write!(
+                    f,
+                    "Failed to delete permission '{}': {}",
+                    permission.clone().id.unwrap_or_default(),
+                    err
+                )
+            }
             Error::PermissionNotFound(id) => {
                 write!(f, "Permission '{}' not found", id)
             }
+            Error::NoMatchingPermissions(filter) => {
+                write!(f, "No permissions found with {}", filter)
+            }
             Error::UnknownPermissionType(type_) => {
                 write!(f, "Unknown permission type: '{}'", type_)
             }
@@ -122,6 +394,74 @@ pub enum RevokeAction {
     Anyone,
     AllExceptOwner,
     Id(String),
+    Matching(PermissionFilter),
+}
+
+// Matches the permissions where all of the given fields match. The owner permission is never matched
+#[derive(Clone, Debug, Default)]
+pub struct PermissionFilter {
+    pub email: Option<String>,
+    // Matches domain permissions and users and groups with an email address in the domain
+    pub domain: Option<String>,
+    pub role: Option<permission::Role>,
+    pub type_: Option<permission::Type>,
+}
+
+impl PermissionFilter {
+    fn matches(&self, permission: &google_drive3::api::Permission) -> bool {
+        let email = permission.email_address.clone().unwrap_or_default();
+        let email_domain = email.rsplit_once('@').map(|(_, domain)| domain.to_string());
+
+        let matches_email = self
+            .email
+            .as_ref()
+            .is_none_or(|wanted| wanted.eq_ignore_ascii_case(&email));
+
+        let matches_domain = self.domain.as_ref().is_none_or(|wanted| {
+            [permission.domain.clone(), email_domain]
+                .iter()
+                .flatten()
+                .any(|domain| wanted.eq_ignore_ascii_case(domain))
+        });
+
+        let matches_role = self
+            .role
+            .as_ref()
+            .is_none_or(|wanted| permission.role == Some(wanted.to_string()));
+
+        let matches_type = self
+            .type_
+            .as_ref()
+            .is_none_or(|wanted| permission.type_ == Some(wanted.to_string()));
+
+        let is_owner = permission.role == Some(permission::Role::Owner.to_string());
+
+        !is_owner && matches_email && matches_domain && matches_role && matches_type
+    }
+}
+
+impl Display for PermissionFilter {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let mut conditions = vec![];
+
+        if let Some(email) = &self.email {
+            conditions.push(format!("email '{}'", email));
+        }
+
+        if let Some(domain) = &self.domain {
+            conditions.push(format!("domain '{}'", domain));
+        }
+
+        if let Some(role) = &self.role {
+            conditions.push(format!("role '{}'", role));
+        }
+
+        if let Some(type_) = &self.type_ {
+            conditions.push(format!("type '{}'", type_));
+        }
+
+        write!(f, "{}", conditions.join(" and "))
+    }
 }
 
 impl RevokeAction {
@@ -152,6 +492,19 @@ impl RevokeAction {
                     .map(|p| vec![p])
                     .ok_or_else(|| Error::PermissionNotFound(id.to_string()))
             }
+
+            RevokeAction::Matching(filter) => {
+                let matching: Vec<google_drive3::api::Permission> = permissions
+                    .into_iter()
+                    .filter(|p| filter.matches(p))
+                    .collect();
+
+                if matching.is_empty() {
+                    Err(Error::NoMatchingPermissions(filter.clone()))
+                } else {
+                    Ok(matching)
+                }
+            }
         }
     }
 
@@ -192,6 +545,19 @@ fn print_revoke_details(
     file: &google_drive3::api::File,
     permission: &google_drive3::api::Permission,
 ) -> Result<(), Error> {
+    println!(
+        "Revoking {} for '{}'",
+        permission_description(permission)?,
+        file.name.clone().unwrap_or_default()
+    );
+
+    Ok(())
+}
+
+// Role and grantee of the permission, like "'reader' permission to 'user' with email 'x'"
+pub fn permission_description(
+    permission: &google_drive3::api::Permission,
+) -> Result<String, Error> {
     let type_: permission::Type = permission
         .type_
         .clone()
@@ -206,30 +572,116 @@ fn print_revoke_details(
         .parse()
         .map_err(|_| Error::UnknownPermissionRole(permission.role.clone().unwrap_or_default()))?;
 
-    if type_.requires_domain() {
-        println!(
-            "Revoking '{}' permission to {} '{}' for '{}'",
+    let description = if type_.requires_domain() {
+        format!(
+            "'{}' permission to {} '{}'",
             role,
             type_,
-            permission.domain.clone().unwrap_or_default(),
-            file.name.clone().unwrap_or_default()
-        );
+            permission.domain.clone().unwrap_or_default()
+        )
     } else if type_.requires_email() {
-        println!(
-            "Revoking '{}' permission to '{}' with email '{}' for '{}'",
+        format!(
+            "'{}' permission to '{}' with email '{}'",
             role,
             type_,
-            permission.email_address.clone().unwrap_or_default(),
-            file.name.clone().unwrap_or_default()
-        );
+            permission.email_address.clone().unwrap_or_default()
+        )
     } else {
-        println!(
-            "Revoking '{}' permission to '{}' for '{}'",
-            role,
-            type_,
-            file.name.clone().unwrap_or_default()
-        );
+        format!("'{}' permission to '{}'", role, type_)
+    };
+
+    Ok(description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(email: &str, role: &str) -> google_drive3::api::Permission {
+        google_drive3::api::Permission {
+            type_: Some(String::from("user")),
+            email_address: Some(email.to_string()),
+            role: Some(role.to_string()),
+            ..google_drive3::api::Permission::default()
+        }
     }
 
-    Ok(())
+    fn domain(domain: &str, role: &str) -> google_drive3::api::Permission {
+        google_drive3::api::Permission {
+            type_: Some(String::from("domain")),
+            domain: Some(domain.to_string()),
+            role: Some(role.to_string()),
+            ..google_drive3::api::Permission::default()
+        }
+    }
+
+    #[test]
+    fn empty_filter_matches_everything_except_the_owner() {
+        let filter = PermissionFilter::default();
+
+        assert!(filter.matches(&user("alice@example.com", "reader")));
+        assert!(filter.matches(&domain("example.com", "writer")));
+        assert!(!filter.matches(&user("owner@example.com", "owner")));
+    }
+
+    #[test]
+    fn email_is_matched_case_insensitively() {
+        let filter = PermissionFilter {
+            email: Some(String::from("Alice@Example.com")),
+            ..PermissionFilter::default()
+        };
+
+        assert!(filter.matches(&user("alice@example.com", "reader")));
+        assert!(!filter.matches(&user("bob@example.com", "reader")));
+        assert!(!filter.matches(&domain("example.com", "reader")));
+    }
+
+    #[test]
+    fn domain_matches_domain_permissions_and_email_addresses() {
+        let filter = PermissionFilter {
+            domain: Some(String::from("EXAMPLE.com")),
+            ..PermissionFilter::default()
+        };
+
+        assert!(filter.matches(&domain("example.com", "reader")));
+        assert!(filter.matches(&user("alice@example.com", "writer")));
+        assert!(!filter.matches(&user("alice@other.com", "writer")));
+    }
+
+    #[test]
+    fn domain_is_not_matched_as_a_suffix() {
+        let filter = PermissionFilter {
+            domain: Some(String::from("example.com")),
+            ..PermissionFilter::default()
+        };
+
+        assert!(!filter.matches(&user("alice@sub.example.com", "reader")));
+        assert!(!filter.matches(&user("alice@notexample.com", "reader")));
+        assert!(!filter.matches(&domain("sub.example.com", "reader")));
+    }
+
+    #[test]
+    fn all_given_fields_must_match() {
+        let filter = PermissionFilter {
+            domain: Some(String::from("example.com")),
+            role: Some(permission::Role::Writer),
+            type_: Some(permission::Type::User),
+            ..PermissionFilter::default()
+        };
+
+        assert!(filter.matches(&user("alice@example.com", "writer")));
+        assert!(!filter.matches(&user("alice@example.com", "reader")));
+        assert!(!filter.matches(&domain("example.com", "writer")));
+    }
+
+    #[test]
+    fn owner_is_never_matched() {
+        let filter = PermissionFilter {
+            email: Some(String::from("owner@example.com")),
+            role: Some(permission::Role::Owner),
+            ..PermissionFilter::default()
+        };
+
+        assert!(!filter.matches(&user("owner@example.com", "owner")));
+    }
 }
@@ -1,9 +1,13 @@
+use crate::common::batch;
 use crate::common::delegate::UploadDelegate;
 use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_batch;
+use crate::common::drive_batch::Call;
 use crate::common::hub_helper;
 use crate::common::permission;
 use crate::files;
 use crate::hub::Hub;
+use crate::permissions::recursive;
 use std::error;
 use std::fmt::Display;
 use std::fmt::Formatter;
@@ -16,6 +20,10 @@ pub struct Config {
     pub discoverable: bool,
     pub email: Option<String>,
     pub domain: Option<String>,
+    // Drive notifies users and groups by default
+    pub notify: Option<bool>,
+    pub message: Option<String>,
+    pub expires: Option<permission::Expiration>,
 }
 
 impl Config {
@@ -33,19 +41,178 @@ impl Config {
 }
 
 pub async fn share(config: Config) -> Result<(), Error> {
-    err_if_missing_email(&config)?;
-    err_if_missing_domain(&config)?;
+    validate(&config)?;
 
     let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
     let delegate_config = UploadDelegateConfig::default();
+    share_file(&hub, delegate_config, &config).await
+}
+
+// Shares each of the files, the file id of the config is ignored.
+// The calls are sent with the batch api to keep the number of requests low
+pub async fn share_many(
+    config: Config,
+    file_ids: Vec<String>,
+    concurrency: usize,
+) -> Result<(), batch::Error> {
+    // Invalid options would fail every file, so check them before starting
+    if let Err(err) = validate(&config) {
+        return Err(batch::Error::InvalidOptions(err.to_string()));
+    }
+
+    let hub = hub_helper::get_hub().await.map_err(batch::Error::Hub)?;
+    let mut summary = batch::Summary::default();
+
+    let get_calls: Vec<Call> = file_ids
+        .iter()
+        .map(|file_id| Call::get_file(file_id, "id,name"))
+        .collect();
+
+    let get_results = drive_batch::execute(&hub, &get_calls, concurrency)
+        .await
+        .map_err(batch::Error::DriveBatch)?;
+
+    let mut files_to_share = vec![];
+
+    for (file_id, result) in file_ids.into_iter().zip(get_results) {
+        match drive_batch::parse::<google_drive3::api::File>(result) {
+            Ok(file) => files_to_share.push((file_id, file)),
+            Err(err) => summary.add_failure(&file_id, &err),
+        }
+    }
+
+    let permission = new_permission(&config);
+    let query = create_query(&config);
+    let query: Vec<(&str, &str)> = query
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect();
+
+    let share_calls = files_to_share
+        .iter()
+        .map(|(file_id, _)| Call::create_permission(file_id, &permission, &query))
+        .collect::<Result<Vec<Call>, drive_batch::Error>>()
+        .map_err(batch::Error::DriveBatch)?;
+
+    let share_results = drive_batch::execute(&hub, &share_calls, concurrency)
+        .await
+        .map_err(batch::Error::DriveBatch)?;
+
+    for ((file_id, file), result) in files_to_share.into_iter().zip(share_results) {
+        match result {
+            Ok(_) => {
+                print_grant_details(&file, &config);
+                summary.add_success();
+            }
+
+            Err(err) => summary.add_failure(&file_id, &err),
+        }
+    }
+
+    summary.finish()
+}
+
+// Shares the files and everything below the folders among them, the file id of the config is ignored.
+// With dry run the items are only listed
+pub async fn share_recursive(
+    config: Config,
+    file_ids: Vec<String>,
+    dry_run: bool,
+    concurrency: usize,
+) -> Result<(), recursive::Error> {
+    let config = with_recursive_notification(config);
+
+    if let Err(err) = err_if_message_without_notify(&config).and_then(|_| validate(&config)) {
+        return Err(recursive::Error::Batch(batch::Error::InvalidOptions(
+            err.to_string(),
+        )));
+    }
+
+    let hub = hub_helper::get_hub().await.map_err(recursive::Error::Hub)?;
+    let items = recursive::collect_items(&hub, &file_ids).await?;
+    let description = grant_description(&config);
+    let total = items.len();
+
+    if dry_run {
+        for (index, item) in items.iter().enumerate() {
+            let message = format!("Would grant {} for '{}'", description, item.label());
+            recursive::print_progress(index + 1, total, &message);
+        }
+
+        return Ok(());
+    }
+
+    let permission = new_permission(&config);
+    let query = create_query(&config);
+    let query: Vec<(&str, &str)> = query
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect();
+
+    let mut summary = batch::Summary::default();
+    let mut done = 0;
+
+    for chunk in items.chunks(recursive::chunk_size(concurrency)) {
+        let share_calls = chunk
+            .iter()
+            .map(|item| Call::create_permission(&item.id, &permission, &query))
+            .collect::<Result<Vec<Call>, drive_batch::Error>>()
+            .map_err(|err| recursive::Error::Batch(batch::Error::DriveBatch(err)))?;
 
-    let file = files::info::get_file(&hub, &config.file_id)
+        let share_results = drive_batch::execute(&hub, &share_calls, concurrency)
+            .await
+            .map_err(|err| recursive::Error::Batch(batch::Error::DriveBatch(err)))?;
+
+        for (item, result) in chunk.iter().zip(share_results) {
+            done += 1;
+
+            match result {
+                Ok(_) => {
+                    let message = format!("Granted {} for '{}'", description, item.label());
+                    recursive::print_progress(done, total, &message);
+                    summary.add_success();
+                }
+
+                Err(err) => summary.add_failure(&item.label(), &err),
+            }
+        }
+    }
+
+    summary.finish().map_err(recursive::Error::Batch)
+}
+
+// Recursive shares can touch thousands of items, grantees are only notified with --notify.
+// Ownership transfers always notify the new owner, so they keep the default of drive
+fn with_recursive_notification(config: Config) -> Config {
+    let notify = if config.type_.supports_notification() && !config.requires_ownership_transfer() {
+        config.notify.or(Some(false))
+    } else {
+        config.notify
+    };
+
+    Config { notify, ..config }
+}
+
+fn err_if_message_without_notify(config: &Config) -> Result<(), Error> {
+    if config.message.is_some() && config.notify != Some(true) {
+        return Err(Error::MessageRequiresNotify);
+    }
+
+    Ok(())
+}
+
+pub async fn share_file(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    config: &Config,
+) -> Result<(), Error> {
+    let file = files::info::get_file(hub, &config.file_id)
         .await
         .map_err(Error::GetFile)?;
 
-    print_grant_details(&file, &config);
+    print_grant_details(&file, config);
 
-    create_permission(&hub, delegate_config, &config)
+    create_permission(hub, delegate_config, config)
         .await
         .map_err(Error::CreatePermission)?;
 
@@ -59,21 +226,22 @@ pub async fn create_permission(
 ) -> Result<google_drive3::api::Permission, google_drive3::Error> {
     let mut delegate = UploadDelegate::new(delegate_config);
 
-    let new_permission = google_drive3::api::Permission {
-        role: Some(config.role.to_string()),
-        type_: Some(config.type_.to_string()),
-        allow_file_discovery: config.allow_file_discovery(),
-        email_address: config.email.clone(),
-        domain: config.domain.clone(),
-        ..google_drive3::api::Permission::default()
-    };
-
-    let (_, permission) = hub
+    let mut req = hub
         .permissions()
-        .create(new_permission, &config.file_id)
+        .create(new_permission(config), &config.file_id);
+
+    if let Some(notify) = config.notify {
+        req = req.send_notification_email(notify);
+    }
+
+    if let Some(message) = &config.message {
+        req = req.email_message(message);
+    }
+
+    let (_, permission) = req
         .param(
             "fields",
-            "id,role,type,domain,emailAddress,allowFileDiscovery",
+            "id,role,type,domain,emailAddress,allowFileDiscovery,expirationTime",
         )
         .transfer_ownership(config.requires_ownership_transfer())
         .add_scope(google_drive3::api::Scope::Full)
@@ -85,6 +253,39 @@ pub async fn create_permission(
     Ok(permission)
 }
 
+fn new_permission(config: &Config) -> google_drive3::api::Permission {
+    google_drive3::api::Permission {
+        role: Some(config.role.to_string()),
+        type_: Some(config.type_.to_string()),
+        allow_file_discovery: config.allow_file_discovery(),
+        email_address: config.email.clone(),
+        domain: config.domain.clone(),
+        expiration_time: config.expires.map(|expires| expires.0),
+        ..google_drive3::api::Permission::default()
+    }
+}
+
+// Query parameters of a create call in a batch
+fn create_query(config: &Config) -> Vec<(&'static str, String)> {
+    let mut query = vec![
+        (
+            "transferOwnership",
+            config.requires_ownership_transfer().to_string(),
+        ),
+        ("fields", String::from("id")),
+    ];
+
+    if let Some(notify) = config.notify {
+        query.push(("sendNotificationEmail", notify.to_string()));
+    }
+
+    if let Some(message) = &config.message {
+        query.push(("emailMessage", message.clone()));
+    }
+
+    query
+}
+
 #[derive(Debug)]
 pub enum Error {
     Hub(hub_helper::Error),
@@ -92,6 +293,11 @@ pub enum Error {
     CreatePermission(google_drive3::Error),
     MissingEmail(permission::Type),
     MissingDomain(permission::Type),
+    InvalidGrant(permission::Error),
+    NotificationNotSupported(permission::Type),
+    OwnerRequiresNotification,
+    MessageWithoutNotification,
+    MessageRequiresNotify,
 }
 
 impl error::Error for Error {}
@@ -120,10 +326,38 @@ impl Display for Error {
                     type_
                 )
             }
+            Error::InvalidGrant(err) => write!(f, "{}", err),
+            Error::NotificationNotSupported(type_) => {
+                write!(
+                    f,
+                    "Notification emails are only sent to users and groups, --notify, --no-notify and --message can't be used with type '{}'",
+                    type_
+                )
+            }
+            Error::OwnerRequiresNotification => {
+                write!(f, "Ownership transfers always notify the new owner, --no-notify can't be used with role 'owner'")
+            }
+            Error::MessageWithoutNotification => {
+                write!(f, "--message can't be used with --no-notify")
+            }
+            Error::MessageRequiresNotify => {
+                write!(f, "Recursive shares don't notify by default, use --notify together with --message")
+            }
         }
     }
 }
 
+// Checks the options against the combinations that drive allows
+pub fn validate(config: &Config) -> Result<(), Error> {
+    err_if_missing_email(config)?;
+    err_if_missing_domain(config)?;
+
+    permission::validate_grant(&config.role, &config.type_, config.expires)
+        .map_err(Error::InvalidGrant)?;
+
+    err_if_invalid_notification(config)
+}
+
 fn err_if_missing_email(config: &Config) -> Result<(), Error> {
     if config.type_.requires_email() && config.email.is_none() {
         return Err(Error::MissingEmail(config.type_.clone()));
@@ -140,29 +374,54 @@ fn err_if_missing_domain(config: &Config) -> Result<(), Error> {
     Ok(())
 }
 
+fn err_if_invalid_notification(config: &Config) -> Result<(), Error> {
+    let has_notification_options = config.notify.is_some() || config.message.is_some();
+
+    if has_notification_options && !config.type_.supports_notification() {
+        return Err(Error::NotificationNotSupported(config.type_.clone()));
+    }
+
+    if config.notify == Some(false) {
+        if config.requires_ownership_transfer() {
+            return Err(Error::OwnerRequiresNotification);
+        }
+
+        if config.message.is_some() {
+            return Err(Error::MessageWithoutNotification);
+        }
+    }
+
+    Ok(())
+}
+
 fn print_grant_details(file: &google_drive3::api::File, config: &Config) {
+    println!(
+        "Granting {} for '{}'",
+        grant_description(config),
+        file.name.clone().unwrap_or_default()
+    );
+
+    if let Some(expires) = config.expires {
+        println!("Permission expires at {}", expires);
+    }
+}
+
+fn grant_description(config: &Config) -> String {
     if config.type_.requires_domain() {
-        println!(
-            "Granting '{}' permission to {} '{}' for '{}'",
+        format!(
+            "'{}' permission to {} '{}'",
             config.role,
             config.type_,
-            config.domain.clone().unwrap_or_default(),
-            file.name.clone().unwrap_or_default()
-        );
+            config.domain.clone().unwrap_or_default()
+        )
     } else if config.type_.requires_email() {
-        println!(
-            "Granting '{}' permission to '{}' with email '{}' for '{}'",
+        format!(
+            "'{}' permission to '{}' with email '{}'",
             config.role,
             config.type_,
-            config.email.clone().unwrap_or_default(),
-            file.name.clone().unwrap_or_default()
-        );
+            config.email.clone().unwrap_or_default()
+        )
     } else {
-        println!(
-            "Granting '{}' permission to '{}' for '{}'",
-            config.role,
-            config.type_,
-            file.name.clone().unwrap_or_default()
-        );
+        format!("'{}' permission to '{}'", config.role, config.type_)
     }
 }
@@ -0,0 +1,308 @@
+use crate::common::delegate::UploadDelegate;
+use crate::common::delegate::UploadDelegateConfig;
+use crate::common::drive_batch;
+use crate::common::drive_batch::Call;
+use crate::common::hub_helper;
+use crate::common::permission;
+use crate::files;
+use crate::hub::Hub;
+use crate::permissions;
+use std::error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+// Drive only transfers the ownership directly within a workspace organization
+const CONSENT_REQUIRED_REASON: &str = "consentRequiredForOwnershipTransfer";
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub file_id: String,
+    pub target: PermissionTarget,
+    pub role: permission::Role,
+    pub expires: Option<permission::Expiration>,
+    pub remove_expiration: bool,
+}
+
+impl Config {
+    fn requires_ownership_transfer(&self) -> bool {
+        self.role == permission::Role::Owner
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum PermissionTarget {
+    Id(String),
+    Email(String),
+}
+
+impl Display for PermissionTarget {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PermissionTarget::Id(id) => write!(f, "id '{}'", id),
+            PermissionTarget::Email(email) => write!(f, "email '{}'", email),
+        }
+    }
+}
+
+pub async fn update(config: Config) -> Result<(), Error> {
+    let hub = hub_helper::get_hub().await.map_err(Error::Hub)?;
+    let delegate_config = UploadDelegateConfig::default();
+
+    let file = files::info::get_file(&hub, &config.file_id)
+        .await
+        .map_err(Error::GetFile)?;
+
+    let permissions =
+        permissions::list::list_permissions(&hub, delegate_config.clone(), &config.file_id)
+            .await
+            .map_err(Error::ListPermissions)?;
+
+    let existing = find_permission(permissions, &config.target)?;
+    err_if_invalid_update(&config, &existing)?;
+
+    print_update_details(&file, &existing, &config);
+
+    if config.requires_ownership_transfer() {
+        return transfer_ownership(&hub, delegate_config, &config, &file, &existing).await;
+    }
+
+    let permission = update_permission(&hub, delegate_config, &config, &existing).await?;
+
+    println!(
+        "Updated permission with id: '{}'",
+        permission.id.unwrap_or_default()
+    );
+
+    Ok(())
+}
+
+// Users outside of the organization of the owner, like consumer accounts, have to accept the
+// ownership. They are made the pending owner when drive asks for their consent
+async fn transfer_ownership(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    config: &Config,
+    file: &google_drive3::api::File,
+    existing: &google_drive3::api::Permission,
+) -> Result<(), Error> {
+    let file_name = file.name.clone().unwrap_or_default();
+
+    match update_permission(hub, delegate_config, config, existing).await {
+        Ok(_) => {
+            println!(
+                "Ownership transferred, the previous owner of '{}' is now a writer",
+                file_name
+            );
+
+            Ok(())
+        }
+
+        Err(Error::UpdatePermission(_, err))
+            if hub_helper::error_reason(&err).as_deref() == Some(CONSENT_REQUIRED_REASON) =>
+        {
+            set_pending_owner(hub, config, existing).await?;
+
+            println!(
+                "'{}' is now the pending owner of '{}' and has to accept the ownership in drive",
+                permissions::list::format_grantee(existing),
+                file_name
+            );
+
+            Ok(())
+        }
+
+        Err(err) => Err(err),
+    }
+}
+
+async fn set_pending_owner(
+    hub: &Hub,
+    config: &Config,
+    existing: &google_drive3::api::Permission,
+) -> Result<(), Error> {
+    let permission_id = existing.id.clone().unwrap_or_default();
+    let call = Call::set_pending_owner(&config.file_id, &permission_id);
+
+    drive_batch::send(hub, &call)
+        .await
+        .map_err(Error::DriveBatch)?
+        .map_err(|err| Error::SetPendingOwner(permission_id, err))?;
+
+    Ok(())
+}
+
+pub async fn update_permission(
+    hub: &Hub,
+    delegate_config: UploadDelegateConfig,
+    config: &Config,
+    existing: &google_drive3::api::Permission,
+) -> Result<google_drive3::api::Permission, Error> {
+    let mut delegate = UploadDelegate::new(delegate_config);
+    let permission_id = existing.id.clone().unwrap_or_default();
+
+    // Only the changed fields are sent, the type and grantee of a permission can't be updated
+    let permission = google_drive3::api::Permission {
+        role: Some(config.role.to_string()),
+        expiration_time: config.expires.map(|expires| expires.0),
+        ..google_drive3::api::Permission::default()
+    };
+
+    let mut req = hub
+        .permissions()
+        .update(permission, &config.file_id, &permission_id);
+
+    if config.remove_expiration {
+        req = req.remove_expiration(true);
+    }
+
+    let (_, permission) = req
+        .param(
+            "fields",
+            "id,role,type,domain,emailAddress,allowFileDiscovery,expirationTime",
+        )
+        .transfer_ownership(config.requires_ownership_transfer())
+        .add_scope(google_drive3::api::Scope::Full)
+        .delegate(&mut delegate)
+        .supports_all_drives(true)
+        .doit()
+        .await
+        .map_err(|err| Error::UpdatePermission(permission_id, err))?;
+
+    Ok(permission)
+}
+
+fn find_permission(
+    permissions: Vec<google_drive3::api::Permission>,
+    target: &PermissionTarget,
+) -> Result<google_drive3::api::Permission, Error> {
+    permissions
+        .into_iter()
+        .find(|p| match target {
+            PermissionTarget::Id(id) => p.id.as_ref() == Some(id),
+            PermissionTarget::Email(email) => p
+                .email_address
+                .as_ref()
+                .map(|address| address.eq_ignore_ascii_case(email))
+                .unwrap_or(false),
+        })
+        .ok_or_else(|| Error::PermissionNotFound(target.clone()))
+}
+
+// Checks the new role and expiration against the type of the existing permission
+fn err_if_invalid_update(
+    config: &Config,
+    existing: &google_drive3::api::Permission,
+) -> Result<(), Error> {
+    let type_: permission::Type = existing
+        .type_
+        .clone()
+        .unwrap_or_default()
+        .parse()
+        .map_err(|_| Error::UnknownPermissionType(existing.type_.clone().unwrap_or_default()))?;
+
+    if existing.role.as_deref() == Some("owner") {
+        return Err(Error::OwnerPermission);
+    }
+
+    permission::validate_grant(&config.role, &type_, config.expires)
+        .map_err(Error::InvalidGrant)?;
+
+    // Drive rejects ownership transfers of permissions that expire
+    let keeps_expiration = existing.expiration_time.is_some() && !config.remove_expiration;
+
+    if config.requires_ownership_transfer() && keeps_expiration {
+        return Err(Error::OwnerWithExpiration);
+    }
+
+    Ok(())
+}
+
+fn print_update_details(
+    file: &google_drive3::api::File,
+    existing: &google_drive3::api::Permission,
+    config: &Config,
+) {
+    let grantee = permissions::list::format_grantee(existing);
+
+    if config.requires_ownership_transfer() {
+        println!(
+            "Transferring ownership of '{}' to '{}'",
+            file.name.clone().unwrap_or_default(),
+            grantee
+        );
+    } else {
+        println!(
+            "Changing '{}' permission of '{}' to '{}' for '{}'",
+            existing.role.clone().unwrap_or_default(),
+            grantee,
+            config.role,
+            file.name.clone().unwrap_or_default()
+        );
+    }
+
+    if let Some(expires) = config.expires {
+        println!("Permission expires at {}", expires);
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hub(hub_helper::Error),
+    GetFile(google_drive3::Error),
+    ListPermissions(google_drive3::Error),
+    UpdatePermission(String, google_drive3::Error),
+    DriveBatch(drive_batch::Error),
+    SetPendingOwner(String, drive_batch::CallError),
+    PermissionNotFound(PermissionTarget),
+    UnknownPermissionType(String),
+    OwnerPermission,
+    InvalidGrant(permission::Error),
+    OwnerWithExpiration,
+}
+
+impl error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Hub(err) => write!(f, "{}", err),
+            Error::GetFile(err) => {
+                write!(f, "Failed to get file: {}", err)
+            }
+            Error::ListPermissions(err) => {
+                write!(f, "Failed to list permissions: {}", err)
+            }
+            Error::UpdatePermission(id, err) => {
+                write!(f, "Failed to update permission '{}': {}", id, err)
+            }
+            Error::DriveBatch(err) => write!(f, "{}", err),
+            Error::SetPendingOwner(id, err) => {
+                write!(
+                    f,
+                    "Failed to make permission '{}' the pending owner: {}",
+                    id, err
+                )
+            }
+            Error::PermissionNotFound(PermissionTarget::Email(email)) => {
+                write!(
+                    f,
+                    "No permission found for email '{}'. Use 'permissions share' to grant a new permission",
+                    email
+                )
+            }
+            Error::PermissionNotFound(target) => {
+                write!(f, "Permission with {} not found", target)
+            }
+            Error::UnknownPermissionType(type_) => {
+                write!(f, "Unknown permission type: '{}'", type_)
+            }
+            Error::OwnerPermission => {
+                write!(f, "The owner permission can't be changed, transfer the ownership to another user instead")
+            }
+            Error::InvalidGrant(err) => write!(f, "{}", err),
+            Error::OwnerWithExpiration => {
+                write!(f, "The permission has an expiration, use --remove-expiration to transfer the ownership")
+            }
+        }
+    }
+}